@@ -0,0 +1,214 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::generator::{GeneratorSpec, LoopSpec};
+use crate::graph::*;
+use crate::{find_ith_progenitor, get_mlg, TRACE};
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether `CountingAlloc` is the global allocator, so that `measure` knows the memory used.
+pub const COUNTING: bool = cfg!(feature = "count-alloc");
+
+/// The system allocator, keeping track of the number of bytes allocated and its peak.
+/// Installed as the global allocator with the `count-alloc` feature.
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let p = System.alloc(layout);
+        if !p.is_null() {
+            let now = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        p
+    }
+
+    unsafe fn dealloc(&self, p: *mut u8, layout: Layout) {
+        System.dealloc(p, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+/// Bytes currently allocated.
+pub fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// Starts measuring the peak from what is allocated now.
+pub fn reset_peak() {
+    PEAK.store(allocated(), Ordering::Relaxed);
+}
+
+/// Most bytes allocated at once since the last `reset_peak`.
+pub fn peak() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+/// The shapes of generated loop graphs benchmarks run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// One node per iteration, each caused by the previous one.
+    Chain,
+    /// Many nodes per iteration that all cause one node, which causes all of them in the next iteration.
+    FanIn,
+    /// Like `test_graph_layered`: two branches of different length within an iteration.
+    Layered,
+}
+
+pub const SHAPES: [Shape; 3] = [Shape::Chain, Shape::FanIn, Shape::Layered];
+
+const FAN_IN: usize = 16;
+
+impl Shape {
+    pub fn parse(s: &str) -> Option<Shape> {
+        SHAPES.iter().copied().find(|shape| shape.to_string() == s)
+    }
+
+    pub fn template(&self) -> LoopSpec {
+        // `spec` chooses the number of iterations
+        let spec = match self {
+            Shape::Chain => "loop A\nA -> A +1".to_owned(),
+            Shape::FanIn => {
+                let mut spec = format!("loop B{}\n", " A".repeat(FAN_IN));
+                for j in 0..FAN_IN {
+                    spec += &format!("A.{j} -> B\nB -> A.{j} +1\n");
+                }
+                spec
+            }
+            Shape::Layered => "loop R B G C C C Y Y
+                B -> R
+                G -> R
+                C.0 -> B
+                C.1 -> C.0
+                C.2 -> C.1
+                Y.0 -> G
+                Y.1 -> Y.0
+                R -> Y.0 +1
+                R -> C.2 +1"
+                .to_owned(),
+        };
+        GeneratorSpec::parse(&(spec + "\niterations 1"))
+            .expect("benchmark templates are well formed")
+            .template
+    }
+
+    /// A loop graph of this shape with about `nodes` nodes.
+    pub fn spec(&self, nodes: usize) -> GeneratorSpec {
+        let template = self.template();
+        let iterations = (nodes / template.nodes.len()).max(2);
+        GeneratorSpec::new(template, iterations)
+    }
+}
+
+impl Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shape::Chain => write!(f, "chain"),
+            Shape::FanIn => write!(f, "fan-in"),
+            Shape::Layered => write!(f, "layered"),
+        }
+    }
+}
+
+/// Time and peak memory of one benchmarked operation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Measurement {
+    pub time: Duration,
+    /// Peak bytes allocated during the operation beyond what was allocated before it,
+    /// if allocations are counted (see `COUNTING`).
+    pub memory: Option<usize>,
+}
+
+impl Display for Measurement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:>9.3}ms ", self.time.as_secs_f64() * 1000.0)?;
+        match self.memory {
+            Some(memory) => write!(f, "{:>8.1}MiB", memory as f64 / (1 << 20) as f64),
+            None => write!(f, "{:>11}", "-"),
+        }
+    }
+}
+
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Measurement) {
+    let before = allocated();
+    reset_peak();
+    let start = Instant::now();
+    let t = f();
+    let time = start.elapsed();
+    let memory = COUNTING.then(|| peak().saturating_sub(before));
+    (t, Measurement { time, memory })
+}
+
+/// Results for one graph.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub shape: Shape,
+    pub nodes: usize,
+    /// `Graph::add` of every node, without edges.
+    pub add: Measurement,
+    /// Generating the graph: adding nodes and edges (which runs `update_depth`) and sorting.
+    pub build: Measurement,
+    /// `find_ith_progenitor` from the shallowest node.
+    pub progenitor: Measurement,
+    /// `get_mlg` from the shallowest node.
+    pub search: Measurement,
+    pub found: bool,
+}
+
+impl Report {
+    pub const HEADER: &'static str = "shape      nodes   Graph::add               build (add + update_depth)   find_ith_progenitor      get_mlg";
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<8} {:>8}   {}   {}       {}   {}{}",
+            self.shape,
+            self.nodes,
+            self.add,
+            self.build,
+            self.progenitor,
+            self.search,
+            if self.found { "" } else { "  (no loop)" }
+        )
+    }
+}
+
+/// Benchmarks one graph of the given shape and size. The search does not print its trace meanwhile.
+pub fn run(shape: Shape, nodes: usize) -> Report {
+    let spec = shape.spec(nodes);
+    let names: Vec<String> = spec.template.nodes.clone();
+    let total = names.len() * spec.iterations;
+
+    let (graph, add) = measure(|| {
+        let mut graph = Graph::new();
+        for k in 0..total {
+            graph.add(Node::new(names[k % names.len()].clone()));
+        }
+        graph
+    });
+    drop(graph);
+
+    let (generated, build) = measure(|| spec.generate());
+    let s = generated.graph.sorted[0].clone();
+
+    let trace = TRACE.swap(false, Ordering::Relaxed);
+    let (_, progenitor) = measure(|| find_ith_progenitor(&s, 1));
+    let (pairs, search) = measure(|| get_mlg(&s));
+    TRACE.store(trace, Ordering::Relaxed);
+
+    Report {
+        shape,
+        nodes: generated.graph.sorted.len(),
+        add,
+        build,
+        progenitor,
+        search,
+        found: pairs.is_some(),
+    }
+}
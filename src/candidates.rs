@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::graph::*;
+use crate::symbol::Symbol;
+
+/// A node worth starting `get_mlg` from.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub node: RNode,
+    /// Most instantiations of the node's quantifier on one chain of causes ending in the node,
+    /// not counting the node itself.
+    pub repetitions: u32,
+    pub depth: u32,
+    pub fan_in: usize,
+    pub score: f64,
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (score {:.2}: {} times among its causes, depth {}, fan-in {})",
+            self.node, self.score, self.repetitions, self.depth, self.fan_in
+        )
+    }
+}
+
+// a node whose quantifier repeats many times among its causes is far into a loop, so `get_mlg`
+// finds the progenitors it needs; consequences and parents only break ties between such nodes
+fn score(repetitions: u32, depth: u32, fan_in: usize) -> f64 {
+    repetitions as f64 + 0.25 * (1.0 + depth as f64).ln() + 0.5 * (1.0 + fan_in as f64).ln()
+}
+
+/// The `k` best nodes to start a search from, best first, at most one per quantifier.
+/// Only nodes whose quantifier occurs among their causes are considered. Expects `sorted` to be sorted.
+pub fn candidates(graph: &Graph, k: usize) -> Vec<Candidate> {
+    // for every node and quantifier, the most instantiations of the quantifier on one chain of
+    // causes ending in the node, counting the node itself
+    let mut all = vec![];
+    graph.fold_causes(|n, parents: &[&HashMap<Symbol, u32>]| {
+        let node = n.borrow();
+        let mut count: HashMap<Symbol, u32> = HashMap::new();
+        for counts in parents {
+            for (q, c) in counts.iter() {
+                let e = count.entry(*q).or_default();
+                *e = (*e).max(*c);
+            }
+        }
+        let repetitions = count.get(&node.symbol).copied().unwrap_or(0);
+        *count.entry(node.symbol).or_default() += 1;
+
+        if repetitions > 0 {
+            all.push(Candidate {
+                node: n.clone(),
+                repetitions,
+                depth: node.depth,
+                fan_in: node.parents.len(),
+                score: score(repetitions, node.depth, node.parents.len()),
+            });
+        }
+        count
+    });
+
+    all.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.node.cmp(&b.node)));
+    let mut best: Vec<Candidate> = vec![];
+    for c in all {
+        if best.len() == k {
+            break;
+        }
+        if !best
+            .iter()
+            .any(|b| b.node.get_symbol() == c.node.get_symbol())
+        {
+            best.push(c);
+        }
+    }
+    best
+}
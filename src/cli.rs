@@ -0,0 +1,278 @@
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::bench;
+use crate::candidates;
+use crate::diff::{self, LoopSummary};
+use crate::edgelist;
+use crate::fix;
+use crate::get_mlg;
+use crate::graph::*;
+use crate::load;
+use crate::normalize::Normalizer;
+use crate::report;
+use crate::severity;
+use crate::template::LoopTemplate;
+use crate::unroll;
+use crate::TRACE;
+
+const USAGE: &str = "usage:
+  mlg search <graph> [<start>]    run the matching loop search from <start> (e.g. A3),
+                                  by default from the best candidate, and suggest where to break
+                                  the loop found
+  mlg rank <graph> [<start>...]   search from each <start> (by default the best candidate of
+                                  every quantifier) and rank the loops found by their cost
+  mlg candidates <graph> [<k>]    suggest <k> (default 5) nodes to start the search from
+  mlg diff <before> <after>       compare the loops ranked in two graphs: new and removed loops
+                                  and changes in shape or number of iterations
+  mlg report <graph> [<out>]      write the stats and ranked loops of the graph to the HTML
+                                  file <out> (default report.html)
+  mlg print <graph>               print the graph in edge-list format
+  mlg stats <graph>               print an overview of the graph
+  mlg bench                       time graph construction and search on generated loop graphs
+                                  of 10^3 up to 10^6 nodes
+options:
+  --normalize <config>            normalize quantifier names with the rules in <config>
+  --trace                         search prints what it is doing
+  --shape <shape>                 bench only chain, fan-in or layered graphs
+  --max-nodes <n>                 bench graphs of up to <n> nodes
+
+graphs are read from .dot/.gv (Graphviz) and .txt/.edges (edge list) files
+or generated from .loop files (loop templates)";
+
+struct Args {
+    positional: Vec<String>,
+    normalize: Option<String>,
+    shape: Option<String>,
+    max_nodes: Option<usize>,
+    trace: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, Box<dyn Error>> {
+    let mut parsed = Args {
+        positional: vec![],
+        normalize: None,
+        shape: None,
+        max_nodes: None,
+        trace: false,
+    };
+    let mut args = args.iter();
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--normalize" => {
+                parsed.normalize = Some(args.next().ok_or("--normalize expects a file")?.clone())
+            }
+            "--trace" => parsed.trace = true,
+            "--shape" => parsed.shape = Some(args.next().ok_or("--shape expects a shape")?.clone()),
+            "--max-nodes" => {
+                let n = args.next().ok_or("--max-nodes expects a number")?;
+                parsed.max_nodes = Some(n.parse().map_err(|_| format!("{} is not a number", n))?)
+            }
+            _ if a.starts_with("--") => {
+                return Err(format!("unknown option {}\n{}", a, USAGE).into())
+            }
+            _ => parsed.positional.push(a.clone()),
+        }
+    }
+    Ok(parsed)
+}
+
+fn load_graph(path: &str, args: &Args) -> Result<Graph, Box<dyn Error>> {
+    let normalizer = match &args.normalize {
+        Some(config) => Some(Normalizer::load(Path::new(config))?),
+        None => None,
+    };
+    Ok(load::from_file(Path::new(path), normalizer)?)
+}
+
+// `A3` -> node 3 of quantifier A, `x1#0` -> node 0 of quantifier x1, as in edge lists
+fn find_node(graph: &Graph, label: &str) -> Result<RNode, Box<dyn Error>> {
+    let (name, id) = edgelist::parse_node(label)
+        .map_err(|_| format!("{} is not a node (expected e.g. A3 or x1#0)", label))?;
+    graph
+        .get(&name, id)
+        .ok_or_else(|| format!("no node {} in graph", label).into())
+}
+
+fn search(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args.positional.get(1).ok_or(USAGE)?;
+    let graph = load_graph(path, args)?;
+    TRACE.store(args.trace, std::sync::atomic::Ordering::Relaxed);
+    let s = match args.positional.get(2) {
+        Some(label) => find_node(&graph, label)?,
+        None => match candidates::candidates(&graph, 1).pop() {
+            Some(c) => c.node,
+            None => graph.sorted.first().ok_or("graph is empty")?.clone(),
+        },
+    };
+
+    match get_mlg(&s) {
+        Some(pairs) => {
+            println!("Pairs");
+            for (n, n_prime) in pairs.iter() {
+                println!("({n},{n_prime}), {:?}", n.get_iteration());
+            }
+            println!("Loop");
+            print!("{}", LoopTemplate::from_pairs(&pairs));
+            print!("{}", unroll::unroll(&pairs));
+            println!("Fix");
+            for suggestion in fix::suggest(&pairs) {
+                print!("{}", suggestion);
+            }
+        }
+        None => println!("No matching loop found from {}", s),
+    }
+    Ok(())
+}
+
+// the best candidate of every quantifier, or the shallowest node if none repeats
+fn default_starts(graph: &Graph) -> Vec<RNode> {
+    let best: Vec<RNode> = candidates::candidates(graph, usize::MAX)
+        .into_iter()
+        .map(|c| c.node)
+        .collect();
+    if best.is_empty() {
+        severity::default_starts(graph)
+    } else {
+        best
+    }
+}
+
+fn rank(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args.positional.get(1).ok_or(USAGE)?;
+    let graph = load_graph(path, args)?;
+    let starts = match &args.positional[2..] {
+        [] => default_starts(&graph),
+        labels => labels
+            .iter()
+            .map(|label| find_node(&graph, label))
+            .collect::<Result<_, _>>()?,
+    };
+
+    let ranked = severity::rank(&graph, &starts);
+    if ranked.is_empty() {
+        println!("No matching loop found");
+    }
+    for (i, loop_) in ranked.iter().enumerate() {
+        println!("#{} {}", i + 1, loop_);
+    }
+    Ok(())
+}
+
+fn suggest(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args.positional.get(1).ok_or(USAGE)?;
+    let k = match args.positional.get(2) {
+        Some(k) => k.parse().map_err(|_| format!("{} is not a number", k))?,
+        None => 5,
+    };
+    let graph = load_graph(path, args)?;
+    let best = candidates::candidates(&graph, k);
+    if best.is_empty() {
+        println!("No quantifier is instantiated by its own consequences");
+    }
+    for (i, c) in best.iter().enumerate() {
+        println!("#{} {}", i + 1, c);
+    }
+    Ok(())
+}
+
+fn compare(args: &Args) -> Result<(), Box<dyn Error>> {
+    let (Some(before), Some(after)) = (args.positional.get(1), args.positional.get(2)) else {
+        return Err(USAGE.into());
+    };
+    let mut loops: Vec<Vec<LoopSummary>> = vec![];
+    for path in [before, after] {
+        let graph = load_graph(path, args)?;
+        let ranked = severity::rank(&graph, &default_starts(&graph));
+        loops.push(ranked.iter().map(LoopSummary::from).collect());
+    }
+
+    let changes = diff::diff(&loops[0], &loops[1]);
+    if changes.is_empty() {
+        println!("No matching loop found in either graph");
+    }
+    for change in changes {
+        print!("{}", change);
+    }
+    Ok(())
+}
+
+fn write_report(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args.positional.get(1).ok_or(USAGE)?;
+    let out = args.positional.get(2).map_or("report.html", |o| o.as_str());
+    let graph = load_graph(path, args)?;
+    let ranked = severity::rank(&graph, &default_starts(&graph));
+    std::fs::write(out, report::write(path, &graph, &ranked))?;
+    println!("Wrote {}", out);
+    Ok(())
+}
+
+fn print(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args.positional.get(1).ok_or(USAGE)?;
+    let graph = load_graph(path, args)?;
+    print!("{}", edgelist::write(&graph));
+    Ok(())
+}
+
+// a size is skipped when one step of it would likely take longer than this
+const BENCH_BUDGET: Duration = Duration::from_secs(1);
+
+fn bench(args: &Args) -> Result<(), Box<dyn Error>> {
+    let shapes = match &args.shape {
+        Some(s) => vec![bench::Shape::parse(s).ok_or_else(|| format!("unknown shape {}", s))?],
+        None => bench::SHAPES.to_vec(),
+    };
+    let max_nodes = args.max_nodes.unwrap_or(1_000_000);
+
+    if !bench::COUNTING {
+        println!("memory is only measured when built with --features count-alloc");
+    }
+    println!("{}", bench::Report::HEADER);
+    for shape in shapes {
+        let mut nodes = 1000;
+        let mut previous: Option<Duration> = None;
+        while nodes <= max_nodes {
+            let report = bench::run(shape, nodes);
+            println!("{}", report);
+            let slowest = [report.add, report.build, report.progenitor, report.search]
+                .iter()
+                .map(|m| m.time)
+                .max()
+                .unwrap_or_default();
+            // ten times the nodes take at least ten times as long, more if the last step grew faster
+            let growth = previous.map_or(10.0, |p| {
+                (slowest.as_secs_f64() / p.as_secs_f64().max(1e-9)).max(10.0)
+            });
+            if slowest.as_secs_f64() * growth > BENCH_BUDGET.as_secs_f64() {
+                println!("{:<8} skipping larger graphs", shape);
+                break;
+            }
+            previous = Some(slowest);
+            nodes *= 10;
+        }
+    }
+    Ok(())
+}
+
+fn stats(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args.positional.get(1).ok_or(USAGE)?;
+    let graph = load_graph(path, args)?;
+    print!("{}", graph.stats());
+    Ok(())
+}
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let args = parse_args(args)?;
+    match args.positional.first().map(|c| c.as_str()) {
+        Some("search") => search(&args),
+        Some("rank") => rank(&args),
+        Some("candidates") => suggest(&args),
+        Some("diff") => compare(&args),
+        Some("report") => write_report(&args),
+        Some("print") => print(&args),
+        Some("stats") => stats(&args),
+        Some("bench") => bench(&args),
+        _ => Err(USAGE.into()),
+    }
+}
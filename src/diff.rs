@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::severity::Severity;
+use crate::template::LoopTemplate;
+
+/// What a diff needs to know about a loop found in one trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopSummary {
+    pub template: LoopTemplate,
+    pub iterations: usize,
+    pub attributable: usize,
+}
+
+impl From<&Severity> for LoopSummary {
+    fn from(s: &Severity) -> LoopSummary {
+        LoopSummary {
+            template: s.template.clone(),
+            iterations: s.unrolled.count(),
+            attributable: s.attributable,
+        }
+    }
+}
+
+impl LoopSummary {
+    // one line, e.g. `A, B×2 (10 iterations, 30 instantiations)`
+    fn line(&self) -> String {
+        let quantifiers: Vec<String> = self
+            .template
+            .quantifiers
+            .iter()
+            .map(|(q, count)| match count {
+                1 => q.clone(),
+                _ => format!("{}×{}", q, count),
+            })
+            .collect();
+        format!(
+            "{} ({} iterations, {} instantiations)",
+            quantifiers.join(", "),
+            self.iterations,
+            self.attributable
+        )
+    }
+}
+
+/// How a loop of one trace relates to the loops of another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// Only in the second trace.
+    New(LoopSummary),
+    /// Only in the first trace.
+    Removed(LoopSummary),
+    /// The same loop, repeated a different number of times.
+    Repetitions {
+        before: LoopSummary,
+        after: LoopSummary,
+    },
+    /// A loop through the same quantifiers that causes them differently.
+    Shape {
+        before: LoopSummary,
+        after: LoopSummary,
+    },
+    Unchanged {
+        before: LoopSummary,
+        after: LoopSummary,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::New(after) => write!(f, "new: {}\n{}", after.line(), after.template),
+            Change::Removed(before) => writeln!(f, "removed: {}", before.line()),
+            Change::Repetitions { before, after } => writeln!(
+                f,
+                "{} {} → {} iterations: {}",
+                if after.iterations < before.iterations {
+                    "shrunk"
+                } else {
+                    "grew"
+                },
+                before.iterations,
+                after.iterations,
+                after.line()
+            ),
+            Change::Shape { before, after } => write!(
+                f,
+                "changed shape: {}\nbefore:\n{}after:\n{}",
+                after.line(),
+                before.template,
+                after.template
+            ),
+            Change::Unchanged { after, .. } => writeln!(f, "unchanged: {}", after.line()),
+        }
+    }
+}
+
+// every edge of the cycle, whether it stays within an iteration or not;
+// where `get_mlg` cut the iterations does not change these
+fn cycle(t: &LoopTemplate) -> BTreeMap<&(String, String), usize> {
+    let mut edges = BTreeMap::new();
+    for (edge, count) in t.intra.iter().chain(t.inter.iter()) {
+        *edges.entry(edge).or_default() += count;
+    }
+    edges
+}
+
+// how closely two templates match, 0 if not at all
+fn likeness(a: &LoopTemplate, b: &LoopTemplate) -> u8 {
+    if a == b {
+        3
+    } else if a.quantifiers == b.quantifiers && cycle(a) == cycle(b) {
+        2
+    } else if a.quantifiers.keys().eq(b.quantifiers.keys()) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Matches the loops of two traces, by template and else by the quantifiers they go through,
+/// and reports what became of each. Loops whose iterations were cut at different places
+/// are the same loop.
+pub fn diff(before: &[LoopSummary], after: &[LoopSummary]) -> Vec<Change> {
+    let mut matched: Vec<Option<usize>> = vec![None; after.len()];
+    let mut taken = vec![false; before.len()];
+    // closer matches first, so a loop that merely changed shape cannot take another's partner
+    for level in (1..=3).rev() {
+        for (j, a) in after.iter().enumerate() {
+            if matched[j].is_some() {
+                continue;
+            }
+            if let Some(i) = (0..before.len())
+                .find(|i| !taken[*i] && likeness(&before[*i].template, &a.template) == level)
+            {
+                taken[i] = true;
+                matched[j] = Some(i);
+            }
+        }
+    }
+
+    let mut changes = vec![];
+    for (j, a) in after.iter().enumerate() {
+        let change = match matched[j] {
+            None => Change::New(a.clone()),
+            Some(i) => {
+                let (before, after) = (before[i].clone(), a.clone());
+                if likeness(&before.template, &after.template) == 1 {
+                    Change::Shape { before, after }
+                } else if before.iterations != after.iterations {
+                    Change::Repetitions { before, after }
+                } else {
+                    Change::Unchanged { before, after }
+                }
+            }
+        };
+        changes.push(change);
+    }
+    for (i, b) in before.iter().enumerate() {
+        if !taken[i] {
+            changes.push(Change::Removed(b.clone()));
+        }
+    }
+    changes
+}
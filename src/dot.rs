@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+
+use crate::graph::*;
+use crate::load::{self, LoadError};
+
+// Reads the subset of the Graphviz DOT language used by hand drawn examples and axiom profiler exports:
+// a `digraph` with node, edge, attribute and (flattened) subgraph statements.
+//
+// Every vertex becomes a `Node`. Its quantifier name is taken from the `quantifier` attribute,
+// else from `label`, else from the vertex id; trailing digits of a label or vertex id are dropped,
+// so that `A3` and `n7 [label="A3"]` both become an instantiation of `A`.
+// Ids are the numbers the names were taken from (`A3` is instantiation 3 of `A`), or else those at
+// the end of the vertex ids; vertices without a number, or whose number is taken, get the next free ids.
+// An edge `u -> v` makes `u` a parent of `v`.
+// The node attributes `qid`, `pattern`, `bind.<var>`, `generation`, `timestamp` and `location`
+// and the edge attributes `kind`, `term` (or `label`) and `equalities` (separated by `;`) are kept,
+// as in edge lists; other attributes are ignored.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Id(String),
+    Arrow,
+    Line,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Eq,
+    Semi,
+    Comma,
+    Colon,
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> LoadError {
+    LoadError::Syntax {
+        line,
+        message: message.into(),
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, LoadError> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+            line_start = true;
+            continue;
+        }
+        if c.is_whitespace() {
+            continue;
+        }
+        // preprocessor output lines
+        if c == '#' && line_start {
+            for c in chars.by_ref() {
+                if c == '\n' {
+                    line += 1;
+                    break;
+                }
+            }
+            continue;
+        }
+        line_start = false;
+
+        let token = match c {
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '=' => Token::Eq,
+            ';' => Token::Semi,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                Token::Arrow
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                Token::Line
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        line_start = true;
+                        break;
+                    }
+                }
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            prev = c;
+                        }
+                        None => return Err(syntax(line, "unterminated comment")),
+                    }
+                }
+                continue;
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('"') => s.push('"'),
+                            Some('\n') => line += 1,
+                            Some(c) => {
+                                s.push('\\');
+                                s.push(c);
+                            }
+                            None => return Err(syntax(line, "unterminated string")),
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            s.push(c);
+                        }
+                        None => return Err(syntax(line, "unterminated string")),
+                    }
+                }
+                Token::Id(s)
+            }
+            '<' => {
+                // HTML string, kept verbatim
+                let mut s = String::new();
+                let mut nesting = 1;
+                loop {
+                    match chars.next() {
+                        Some('>') if nesting == 1 => break,
+                        Some(c) => {
+                            match c {
+                                '<' => nesting += 1,
+                                '>' => nesting -= 1,
+                                '\n' => line += 1,
+                                _ => {}
+                            }
+                            s.push(c);
+                        }
+                        None => return Err(syntax(line, "unterminated HTML string")),
+                    }
+                }
+                Token::Id(s)
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut s = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' {
+                        s.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Id(s)
+            }
+            c => return Err(syntax(line, format!("unexpected character `{}`", c))),
+        };
+        tokens.push((token, line));
+    }
+
+    Ok(tokens)
+}
+
+type Attrs = HashMap<String, String>;
+
+#[derive(Default)]
+struct Parsed {
+    /// Each with the line of the last statement that set its attributes.
+    vertices: Vec<(String, Attrs, usize)>,
+    index: HashMap<String, usize>,
+    edges: Vec<(String, String, Attrs, usize)>,
+}
+
+impl Parsed {
+    // defaults only apply to vertices not seen before
+    fn vertex(&mut self, id: &str, defaults: &Attrs, attrs: &Attrs, line: usize) {
+        let i = match self.index.get(id) {
+            Some(i) => *i,
+            None => {
+                self.index.insert(id.to_owned(), self.vertices.len());
+                self.vertices.push((id.to_owned(), defaults.clone(), line));
+                self.vertices.len() - 1
+            }
+        };
+        let vertex = &mut self.vertices[i];
+        if !attrs.is_empty() {
+            vertex.2 = line;
+        }
+        vertex
+            .1
+            .extend(attrs.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    node_defaults: Attrs,
+    edge_defaults: Attrs,
+    parsed: Parsed,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn lookahead(&self) -> Option<&Token> {
+        self.tokens.get(self.pos + 1).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, l)| *l)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.peek().cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn expect(&mut self, t: Token) -> Result<(), LoadError> {
+        let line = self.line();
+        match self.next() {
+            Some(u) if u == t => Ok(()),
+            Some(u) => Err(syntax(line, format!("expected {:?}, found {:?}", t, u))),
+            None => Err(syntax(
+                line,
+                format!("expected {:?}, found end of input", t),
+            )),
+        }
+    }
+
+    fn id(&mut self) -> Result<String, LoadError> {
+        let line = self.line();
+        match self.next() {
+            Some(Token::Id(s)) => Ok(s),
+            Some(u) => Err(syntax(
+                line,
+                format!("expected an identifier, found {:?}", u),
+            )),
+            None => Err(syntax(line, "expected an identifier, found end of input")),
+        }
+    }
+
+    fn keyword(&self, k: &str) -> bool {
+        matches!(self.peek(), Some(Token::Id(s)) if s.eq_ignore_ascii_case(k))
+    }
+
+    fn graph(&mut self) -> Result<(), LoadError> {
+        if self.keyword("strict") {
+            self.next();
+        }
+        if !self.keyword("digraph") {
+            return Err(syntax(self.line(), "expected `digraph`"));
+        }
+        self.next();
+        if let Some(Token::Id(_)) = self.peek() {
+            self.next();
+        }
+        self.expect(Token::LBrace)?;
+        self.stmts()?;
+        self.expect(Token::RBrace)?;
+        match self.peek() {
+            None => Ok(()),
+            Some(t) => Err(syntax(
+                self.line(),
+                format!("unexpected {:?} after graph", t),
+            )),
+        }
+    }
+
+    // returns the vertices mentioned, for edges to and from subgraphs
+    fn stmts(&mut self) -> Result<Vec<String>, LoadError> {
+        let mut mentioned = vec![];
+        while !matches!(self.peek(), Some(Token::RBrace) | None) {
+            mentioned.extend(self.stmt()?);
+            while matches!(self.peek(), Some(Token::Semi) | Some(Token::Comma)) {
+                self.next();
+            }
+        }
+        Ok(mentioned)
+    }
+
+    fn stmt(&mut self) -> Result<Vec<String>, LoadError> {
+        // attribute statements `graph [...]`, `node [...]` and `edge [...]`
+        if let Some(Token::Id(k)) = self.peek().cloned() {
+            let k = k.to_ascii_lowercase();
+            if matches!(k.as_str(), "graph" | "node" | "edge")
+                && self.lookahead() == Some(&Token::LBracket)
+            {
+                self.next();
+                let attrs = self.attr_lists()?;
+                match k.as_str() {
+                    "node" => self.node_defaults.extend(attrs),
+                    "edge" => self.edge_defaults.extend(attrs),
+                    _ => {}
+                }
+                return Ok(vec![]);
+            }
+        }
+
+        // graph attribute `a = b`
+        if let (Some(Token::Id(_)), Some(Token::Eq)) = (self.peek(), self.lookahead()) {
+            self.next();
+            self.next();
+            self.id()?;
+            return Ok(vec![]);
+        }
+
+        let line = self.line();
+        let mut ends = vec![self.endpoint()?];
+        loop {
+            match self.peek() {
+                Some(Token::Arrow) => {
+                    self.next();
+                    ends.push(self.endpoint()?);
+                }
+                Some(Token::Line) => {
+                    return Err(syntax(self.line(), "undirected edges are not supported"));
+                }
+                _ => break,
+            }
+        }
+
+        let attrs = if self.peek() == Some(&Token::LBracket) {
+            self.attr_lists()?
+        } else {
+            Attrs::new()
+        };
+
+        if ends.len() == 1 {
+            for v in ends[0].iter() {
+                self.parsed.vertex(v, &self.node_defaults, &attrs, line);
+            }
+        } else {
+            let mut edge_attrs = self.edge_defaults.clone();
+            edge_attrs.extend(attrs);
+            for w in ends.windows(2) {
+                for u in w[0].iter() {
+                    for v in w[1].iter() {
+                        self.parsed
+                            .edges
+                            .push((u.clone(), v.clone(), edge_attrs.clone(), line));
+                    }
+                }
+            }
+        }
+
+        Ok(ends.concat())
+    }
+
+    // a vertex (with optional port) or a subgraph
+    fn endpoint(&mut self) -> Result<Vec<String>, LoadError> {
+        if self.keyword("subgraph") || self.peek() == Some(&Token::LBrace) {
+            if self.keyword("subgraph") {
+                self.next();
+                if let Some(Token::Id(_)) = self.peek() {
+                    self.next();
+                }
+            }
+            self.expect(Token::LBrace)?;
+            let mentioned = self.stmts()?;
+            self.expect(Token::RBrace)?;
+            return Ok(mentioned);
+        }
+
+        let line = self.line();
+        let id = self.id()?;
+        while self.peek() == Some(&Token::Colon) {
+            self.next();
+            self.id()?;
+        }
+        self.parsed
+            .vertex(&id, &self.node_defaults, &Attrs::new(), line);
+        Ok(vec![id])
+    }
+
+    fn attr_lists(&mut self) -> Result<Attrs, LoadError> {
+        let mut attrs = Attrs::new();
+        while self.peek() == Some(&Token::LBracket) {
+            self.next();
+            while self.peek() != Some(&Token::RBracket) {
+                let k = self.id()?;
+                self.expect(Token::Eq)?;
+                let v = self.id()?;
+                attrs.insert(k, v);
+                while matches!(self.peek(), Some(Token::Semi) | Some(Token::Comma)) {
+                    self.next();
+                }
+            }
+            self.expect(Token::RBracket)?;
+        }
+        Ok(attrs)
+    }
+}
+
+// `A12` -> ("A", Some(12)); a name made up only of digits is kept whole
+fn split_id(s: &str) -> (&str, Option<u64>) {
+    let stem = s.trim_end_matches(|c: char| c.is_ascii_digit());
+    if stem.is_empty() || stem.len() == s.len() {
+        return (s, None);
+    }
+    (stem, s[stem.len()..].parse().ok())
+}
+
+// other attributes, e.g. `color`, are none of ours
+fn node_attributes(attrs: &Attrs, line: usize) -> Result<Attributes, LoadError> {
+    let mut a = Attributes::default();
+    // sorted, so that bindings come in the same order every time
+    let mut attrs: Vec<(&String, &String)> = attrs.iter().collect();
+    attrs.sort();
+    for (k, v) in attrs {
+        load::node_attribute(&mut a, k, v, line)?;
+    }
+    Ok(a)
+}
+
+fn edge_label(attrs: &Attrs, line: usize) -> Result<Option<EdgeLabel>, LoadError> {
+    let term = attrs.get("term").or(attrs.get("label")).cloned();
+    let equalities: Vec<String> = attrs
+        .get("equalities")
+        .map(|e| {
+            e.split(';')
+                .map(|s| s.trim().to_owned())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    let kind = match attrs.get("kind") {
+        Some(k) => load::dependency_kind(k, line)?,
+        None if term.is_none() && equalities.is_empty() => return Ok(None),
+        None => DependencyKind::Trigger,
+    };
+    Ok(Some(EdgeLabel {
+        kind,
+        term,
+        equalities,
+    }))
+}
+
+/// Reads a DOT digraph into a new `Graph`.
+pub fn parse(text: &str) -> Result<Graph, LoadError> {
+    parse_into(text, Graph::new())
+}
+
+/// Reads a DOT digraph into `graph` (e.g. one created with `Graph::with_normalizer`).
+pub fn parse_into(text: &str, mut graph: Graph) -> Result<Graph, LoadError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        node_defaults: Attrs::new(),
+        edge_defaults: Attrs::new(),
+        parsed: Parsed::default(),
+    };
+    parser.graph()?;
+    let parsed = parser.parsed;
+
+    let mut order: Vec<(String, Option<u64>, usize)> = parsed
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(i, (vid, attrs, _))| {
+            let (name, num) = match attrs.get("quantifier") {
+                Some(q) => (q.as_str(), split_id(vid).1),
+                None => {
+                    let (name, num) = split_id(attrs.get("label").unwrap_or(vid));
+                    (name, num.or(split_id(vid).1))
+                }
+            };
+            (name.to_owned(), num, i)
+        })
+        .collect();
+    order.sort();
+
+    let mut nodes: HashMap<String, RNode> = HashMap::new();
+    let mut unnumbered = vec![];
+    for (name, num, i) in order {
+        let (vid, attrs, line) = &parsed.vertices[i];
+        let node = Node::new(name.clone()).with_attributes(node_attributes(attrs, *line)?);
+        match num.and_then(|num| u32::try_from(num).ok()) {
+            Some(id) => match graph.add_with_id(node, id) {
+                Some(n) => {
+                    nodes.insert(vid.clone(), n);
+                }
+                None => unnumbered.push((name, i)),
+            },
+            None => unnumbered.push((name, i)),
+        }
+    }
+    for (name, i) in unnumbered {
+        let (vid, attrs, line) = &parsed.vertices[i];
+        let n = graph.add(Node::new(name).with_attributes(node_attributes(attrs, *line)?));
+        nodes.insert(vid.clone(), n);
+    }
+
+    for (u, v, attrs, line) in parsed.edges.iter() {
+        let (parent, child) = (&nodes[u], &nodes[v]);
+        child.try_add_edge(parent)?;
+        if let Some(label) = edge_label(attrs, *line)? {
+            child.set_edge_label(parent, label);
+        }
+    }
+
+    load::finish(graph)
+}
@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::graph::*;
+use crate::load::{self, LoadError};
+
+// A line based format for small graphs, meant to be written by hand:
+//
+//     # the first iteration of a loop
+//     A0 -> B0 -> C0
+//     A1 -> B0 [term="f(a1)"]
+//     D0 [pattern="{g(x)}" bind.x="a1"]
+//
+// Each line is a node or a chain of edges `u -> v -> w`, where `u -> v` makes `u` a parent of `v`;
+// the order of a node's parents is the order in which its incoming edges appear.
+// A node is written as its quantifier name directly followed by its id (`A0`),
+// or as `name#id` if the name ends in a digit, or as `"name"#id` if it contains spaces or quotes.
+// An optional `[key=value ...]` list sets node attributes (`qid`, `pattern`, `bind.<var>`,
+// `generation`, `timestamp`, `location`) on a single node or edge labels (`kind`, `term`,
+// `equalities`, the latter separated by `;`) on every edge of a chain.
+// Ids are kept as written, so `A3 -> A5` is read and printed back as is.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    // quoted name and id
+    Node(String, u32),
+    Quoted(String),
+    Arrow,
+    LBracket,
+    RBracket,
+    Eq,
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> LoadError {
+    LoadError::Syntax {
+        line,
+        message: message.into(),
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '[' | ']' | '=' | '"')
+}
+
+fn arrow_ahead(chars: &std::iter::Peekable<std::str::Chars<'_>>) -> bool {
+    let mut ahead = chars.clone();
+    ahead.next() == Some('-') && ahead.next() == Some('>')
+}
+
+fn tokenize(text: &str, line: usize) -> Result<Vec<Token>, LoadError> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '#' => break,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '=' => Token::Eq,
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => s.push(
+                            chars
+                                .next()
+                                .ok_or_else(|| syntax(line, "unterminated string"))?,
+                        ),
+                        Some(c) => s.push(c),
+                        None => return Err(syntax(line, "unterminated string")),
+                    }
+                }
+                if chars.peek() == Some(&'#') {
+                    chars.next();
+                    let mut id = String::new();
+                    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                        id.push(c);
+                    }
+                    let id = id
+                        .parse()
+                        .map_err(|_| syntax(line, format!("expected an id after \"{}\"#", s)))?;
+                    Token::Node(s, id)
+                } else {
+                    Token::Quoted(s)
+                }
+            }
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                Token::Arrow
+            }
+            c => {
+                let mut s = String::from(c);
+                // `A0->B0` is an edge too
+                while !arrow_ahead(&chars) {
+                    let Some(c) = chars.next_if(|c| is_word_char(*c)) else {
+                        break;
+                    };
+                    s.push(c);
+                }
+                Token::Word(s)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+// `A12` -> ("A", 12), `x1#0` -> ("x1", 0)
+fn split_node(s: &str, line: usize) -> Result<(String, u32), LoadError> {
+    let (name, id) = match s.rsplit_once('#') {
+        Some((name, id)) => (name, id),
+        None => {
+            let name = s.trim_end_matches(|c: char| c.is_ascii_digit());
+            (name, &s[name.len()..])
+        }
+    };
+    match id.parse() {
+        Ok(id) if !name.is_empty() => Ok((name.to_owned(), id)),
+        _ => Err(syntax(
+            line,
+            format!("`{}` is not a node (expected e.g. A0)", s),
+        )),
+    }
+}
+
+/// Reads a node as the edge-list writer prints it, e.g. `A3`, `x1#0` or `"my quantifier"#0`.
+pub fn parse_node(label: &str) -> Result<(String, u32), LoadError> {
+    match tokenize(label, 1)?.as_slice() {
+        [Token::Word(w)] => split_node(w, 1),
+        [Token::Node(name, id)] => Ok((name.clone(), *id)),
+        _ => Err(syntax(
+            1,
+            format!("`{}` is not a node (expected e.g. A0)", label),
+        )),
+    }
+}
+
+type Attrs = Vec<(String, String)>;
+
+struct Line {
+    nodes: Vec<(String, u32)>,
+    attrs: Attrs,
+    number: usize,
+}
+
+fn parse_line(text: &str, number: usize) -> Result<Option<Line>, LoadError> {
+    let mut tokens = tokenize(text, number)?.into_iter().peekable();
+    if tokens.peek().is_none() {
+        return Ok(None);
+    }
+
+    let mut nodes = vec![];
+    loop {
+        match tokens.next() {
+            Some(Token::Word(w)) => nodes.push(split_node(&w, number)?),
+            Some(Token::Node(name, id)) => nodes.push((name, id)),
+            _ => return Err(syntax(number, "expected a node")),
+        }
+        if tokens.next_if_eq(&Token::Arrow).is_none() {
+            break;
+        }
+    }
+
+    let mut attrs = vec![];
+    if tokens.next_if_eq(&Token::LBracket).is_some() {
+        loop {
+            let key = match tokens.next() {
+                Some(Token::RBracket) => break,
+                Some(Token::Word(k)) => k,
+                _ => return Err(syntax(number, "expected `key=value` or `]`")),
+            };
+            if tokens.next() != Some(Token::Eq) {
+                return Err(syntax(number, format!("expected `=` after {}", key)));
+            }
+            match tokens.next() {
+                Some(Token::Word(v)) | Some(Token::Quoted(v)) => attrs.push((key, v)),
+                _ => return Err(syntax(number, format!("expected a value for {}", key))),
+            }
+        }
+    }
+    if let Some(t) = tokens.next() {
+        return Err(syntax(number, format!("unexpected {:?}", t)));
+    }
+
+    Ok(Some(Line {
+        nodes,
+        attrs,
+        number,
+    }))
+}
+
+fn node_attributes(attrs: &Attrs, line: usize) -> Result<Attributes, LoadError> {
+    let mut a = Attributes::default();
+    for (k, v) in attrs.iter() {
+        if !load::node_attribute(&mut a, k, v, line)? {
+            return Err(syntax(line, format!("unknown node attribute {}", k)));
+        }
+    }
+    Ok(a)
+}
+
+fn edge_label(attrs: &Attrs, line: usize) -> Result<Option<EdgeLabel>, LoadError> {
+    if attrs.is_empty() {
+        return Ok(None);
+    }
+    let mut label = EdgeLabel {
+        kind: DependencyKind::Trigger,
+        term: None,
+        equalities: vec![],
+    };
+    for (k, v) in attrs.iter() {
+        match k.as_str() {
+            "kind" => label.kind = load::dependency_kind(v, line)?,
+            "term" => label.term = Some(v.clone()),
+            "equalities" => {
+                label.equalities = v
+                    .split(';')
+                    .map(|e| e.trim().to_owned())
+                    .filter(|e| !e.is_empty())
+                    .collect()
+            }
+            _ => return Err(syntax(line, format!("unknown edge attribute {}={}", k, v))),
+        }
+    }
+    Ok(Some(label))
+}
+
+/// Reads a graph in edge-list format into a new `Graph`.
+pub fn parse(text: &str) -> Result<Graph, LoadError> {
+    parse_into(text, Graph::new())
+}
+
+/// Reads a graph in edge-list format into `graph` (e.g. one created with `Graph::with_normalizer`).
+pub fn parse_into(text: &str, mut graph: Graph) -> Result<Graph, LoadError> {
+    let mut lines = vec![];
+    for (i, l) in text.lines().enumerate() {
+        if let Some(line) = parse_line(l, i + 1)? {
+            lines.push(line);
+        }
+    }
+
+    let mut names: Vec<(String, u32)> =
+        lines.iter().flat_map(|l| l.nodes.iter().cloned()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut attributes: HashMap<(String, u32), Attributes> = HashMap::new();
+    for line in lines.iter().filter(|l| l.nodes.len() == 1) {
+        let a = node_attributes(&line.attrs, line.number)?;
+        if !a.is_empty() {
+            attributes.insert(line.nodes[0].clone(), a);
+        }
+    }
+
+    let mut nodes: HashMap<(String, u32), RNode> = HashMap::new();
+    // names that only differ before normalization can ask for the same id, the later ones get new ids
+    let mut taken = vec![];
+    for key in names {
+        let mut node = Node::new(key.0.clone());
+        if let Some(a) = attributes.get(&key) {
+            node = node.with_attributes(a.clone());
+        }
+        match graph.add_with_id(node, key.1) {
+            Some(n) => {
+                nodes.insert(key, n);
+            }
+            None => taken.push(key),
+        }
+    }
+    for key in taken {
+        let mut node = Node::new(key.0.clone());
+        if let Some(a) = attributes.remove(&key) {
+            node = node.with_attributes(a);
+        }
+        nodes.insert(key, graph.add(node));
+    }
+
+    for line in lines.iter().filter(|l| l.nodes.len() > 1) {
+        let label = edge_label(&line.attrs, line.number)?;
+        for w in line.nodes.windows(2) {
+            let (parent, child) = (&nodes[&w[0]], &nodes[&w[1]]);
+            child.try_add_edge(parent)?;
+            if let Some(label) = &label {
+                child.set_edge_label(parent, label.clone());
+            }
+        }
+    }
+
+    load::finish(graph)
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn node_token(n: &RNode) -> String {
+    let name = n.get_name();
+    if name.is_empty()
+        || !name.chars().all(is_word_char)
+        || name.contains('#')
+        || name.contains("->")
+    {
+        format!("{}#{}", quote(&name), n.get_id())
+    } else if name.ends_with(|c: char| c.is_ascii_digit()) {
+        format!("{}#{}", name, n.get_id())
+    } else {
+        format!("{}{}", name, n.get_id())
+    }
+}
+
+fn value(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(is_word_char) && !s.starts_with('#') && !s.contains("->") {
+        s.to_owned()
+    } else {
+        quote(s)
+    }
+}
+
+fn attr_list(attrs: Attrs) -> String {
+    if attrs.is_empty() {
+        return String::new();
+    }
+    let attrs: Vec<String> = attrs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, value(v)))
+        .collect();
+    format!(" [{}]", attrs.join(" "))
+}
+
+/// Writes `graph` in edge-list format, such that `parse` gives back the same graph.
+pub fn write(graph: &Graph) -> String {
+    let mut names: Vec<&String> = graph.nodes.keys().collect();
+    names.sort();
+
+    let mut s = String::new();
+    for name in names {
+        for n in graph.nodes[name].iter() {
+            let a = n.get_attributes();
+            if a.is_some() || (n.get_parents().is_empty() && n.get_children().is_empty()) {
+                let mut attrs = vec![];
+                if let Some(a) = a {
+                    if let Some(qid) = a.quantifier_id {
+                        attrs.push(("qid".to_owned(), qid));
+                    }
+                    if let Some(pattern) = a.pattern {
+                        attrs.push(("pattern".to_owned(), pattern));
+                    }
+                    for (x, t) in a.bindings {
+                        attrs.push((format!("bind.{}", x), t));
+                    }
+                    if let Some(generation) = a.generation {
+                        attrs.push(("generation".to_owned(), generation.to_string()));
+                    }
+                    if let Some(timestamp) = a.timestamp {
+                        attrs.push(("timestamp".to_owned(), timestamp.to_string()));
+                    }
+                    if let Some(location) = a.location {
+                        attrs.push(("location".to_owned(), location));
+                    }
+                }
+                writeln!(s, "{}{}", node_token(n), attr_list(attrs)).unwrap();
+            }
+
+            for p in n.get_parents() {
+                let mut attrs = vec![];
+                if let Some(l) = n.get_edge_label(&p) {
+                    if l.kind == DependencyKind::Equality {
+                        attrs.push(("kind".to_owned(), "equality".to_owned()));
+                    }
+                    if let Some(term) = l.term {
+                        attrs.push(("term".to_owned(), term));
+                    }
+                    if !l.equalities.is_empty() {
+                        attrs.push(("equalities".to_owned(), l.equalities.join("; ")));
+                    }
+                    if attrs.is_empty() {
+                        attrs.push(("kind".to_owned(), "trigger".to_owned()));
+                    }
+                }
+                writeln!(
+                    s,
+                    "{} -> {}{}",
+                    node_token(&p),
+                    node_token(n),
+                    attr_list(attrs)
+                )
+                .unwrap();
+            }
+        }
+    }
+    s
+}
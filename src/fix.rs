@@ -0,0 +1,117 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use crate::graph::*;
+
+/// A quantifier whose trigger could be changed to break a matching loop: its instantiations are
+/// triggered by terms the previous iteration produced.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// The quantifier to change the trigger of.
+    pub quantifier: String,
+    /// Its trigger pattern, if the trace has it.
+    pub pattern: Option<String>,
+    /// The quantifiers of the previous iteration whose instantiations produced the triggering terms.
+    pub producers: BTreeSet<String>,
+    /// The edges `(producer, triggered)` from the previous iteration into the quantifier.
+    pub edges: Vec<(RNode, RNode)>,
+    /// Whether every edge into the next iteration ends in the quantifier,
+    /// so that no iteration can follow another once it no longer matches.
+    pub breaks_loop: bool,
+}
+
+impl Suggestion {
+    /// The terms that triggered the quantifier, as far as the edge labels say.
+    pub fn terms(&self) -> Vec<String> {
+        let mut terms = vec![];
+        for (p, c) in self.edges.iter() {
+            if let Some(term) = c.get_edge_label(p).and_then(|l| l.term) {
+                if !terms.contains(&term) {
+                    terms.push(term);
+                }
+            }
+        }
+        terms
+    }
+
+    // the edges with a term to show first
+    fn examples(&self) -> impl Iterator<Item = &(RNode, RNode)> {
+        let labeled =
+            |(p, c): &&(RNode, RNode)| c.get_edge_label(p).is_some_and(|l| l.term.is_some());
+        self.edges
+            .iter()
+            .filter(labeled)
+            .chain(self.edges.iter().filter(move |e| !labeled(e)))
+    }
+}
+
+impl Display for Suggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "change the trigger of {}", self.quantifier)?;
+        if let Some(pattern) = &self.pattern {
+            write!(f, " {}", pattern)?;
+        }
+        let producers: Vec<&str> = self.producers.iter().map(|p| p.as_str()).collect();
+        writeln!(
+            f,
+            ", matched by terms from {} of the previous iteration{}",
+            producers.join(", "),
+            if self.breaks_loop {
+                ""
+            } else {
+                " (breaks only some of the dependencies between iterations)"
+            }
+        )?;
+        let Some((p, c)) = self.examples().next() else {
+            return Ok(());
+        };
+        match c.get_edge_label(p) {
+            Some(label) => writeln!(f, "  {}", label.explain(p, c))?,
+            None => writeln!(f, "  {} was triggered by a term produced by {}", c, p)?,
+        }
+        if let Some(a) = c.get_attributes().filter(|a| !a.bindings.is_empty()) {
+            let bindings: Vec<String> = a
+                .bindings
+                .iter()
+                .map(|(x, t)| format!("{} := {}", x, t))
+                .collect();
+            writeln!(f, "  with {}", bindings.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Where to break a result of `get_mlg`: the quantifiers triggered by the previous iteration,
+/// those that break the loop on their own first, then those with a known triggering term.
+/// The iterations `get_mlg` assigned must still be set on the nodes.
+#[allow(clippy::mutable_key_type)]
+pub fn suggest(pairs: &BTreeMap<RNode, RNode>) -> Vec<Suggestion> {
+    // like the template, iteration 1 of the pairing causes iteration 0
+    let mut by_quantifier: BTreeMap<String, Vec<(RNode, RNode)>> = BTreeMap::new();
+    for c in pairs.keys().filter(|n| n.get_iteration() == Some(0)) {
+        for p in c.get_parents() {
+            if p.get_iteration() == Some(1) {
+                by_quantifier
+                    .entry(c.get_name())
+                    .or_default()
+                    .push((p, c.clone()));
+            }
+        }
+    }
+
+    let only = by_quantifier.len() == 1;
+    let mut suggestions: Vec<Suggestion> = by_quantifier
+        .into_iter()
+        .map(|(quantifier, edges)| Suggestion {
+            pattern: edges
+                .iter()
+                .find_map(|(_, c)| c.get_attributes().and_then(|a| a.pattern)),
+            producers: edges.iter().map(|(p, _)| p.get_name()).collect(),
+            quantifier,
+            edges,
+            breaks_loop: only,
+        })
+        .collect();
+    suggestions.sort_by_key(|s| (!s.breaks_loop, s.terms().is_empty()));
+    suggestions
+}
@@ -1,6 +1,7 @@
 use std::cmp::Reverse;
 use std::collections::{BTreeSet, BinaryHeap};
 
+#[derive(Default)]
 pub struct MinHeap<T>(BinaryHeap<Reverse<T>>)
 where
     T: Ord + Clone;
@@ -38,6 +39,7 @@ where
     }
 }
 
+#[derive(Default)]
 pub struct MinSet<T>(BTreeSet<T>)
 where
     T: Ord + Clone;
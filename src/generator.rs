@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use crate::graph::*;
+use crate::load::{self, LoadError};
+
+/// Small deterministic random number generator (SplitMix64),
+/// so that generated graphs only depend on their seed.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`; `n` must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// True with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+/// The shape of one iteration of a matching loop.
+/// Nodes of an iteration are referred to by their index in `nodes`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoopSpec {
+    /// Quantifier name of each node of an iteration.
+    pub nodes: Vec<String>,
+    /// Edges `(parent, child)` within one iteration.
+    pub intra: Vec<(usize, usize)>,
+    /// Edges `(parent, child, lag)` from a node of iteration `i` to a node of iteration `i + lag`.
+    pub inter: Vec<(usize, usize, u32)>,
+}
+
+/// A family member of generated matching loop graphs: `iterations` copies of `template`,
+/// optionally caused by a `prefix` chain, followed by a `suffix` chain and decorated with `noise`
+/// nodes (consequences of random nodes that do not take part in the loop).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeneratorSpec {
+    pub template: LoopSpec,
+    pub iterations: usize,
+    pub prefix: Vec<String>,
+    pub suffix: Vec<String>,
+    pub noise: usize,
+    pub seed: u64,
+}
+
+/// The nodes of a generated graph, by role.
+#[derive(Debug)]
+pub struct Generated {
+    pub graph: Graph,
+    /// `iterations[i][j]` is node `j` of the template in iteration `i`, in causal order.
+    pub iterations: Vec<Vec<RNode>>,
+    pub prefix: Vec<RNode>,
+    pub suffix: Vec<RNode>,
+    pub noise: Vec<RNode>,
+}
+
+pub const NOISE_NAMES: [&str; 3] = ["noise_a", "noise_b", "noise_c"];
+
+// whether the edges `(parent, child)` lead from `from` to `to`
+fn reaches(edges: &[(usize, usize)], from: usize, to: usize) -> bool {
+    let mut stack = vec![from];
+    let mut seen = vec![from];
+    while let Some(n) = stack.pop() {
+        if n == to {
+            return true;
+        }
+        for (_, c) in edges.iter().filter(|(p, _)| *p == n) {
+            if !seen.contains(c) {
+                seen.push(*c);
+                stack.push(*c);
+            }
+        }
+    }
+    false
+}
+
+impl GeneratorSpec {
+    pub fn new(template: LoopSpec, iterations: usize) -> GeneratorSpec {
+        GeneratorSpec {
+            template,
+            iterations,
+            ..Default::default()
+        }
+    }
+
+    /// Parses the generator DSL, one directive per line (`#` starts a comment):
+    ///
+    /// ```text
+    /// loop A B C D      # the nodes of one iteration
+    /// D -> A            # edge within an iteration
+    /// A -> B
+    /// C -> B
+    /// C -> A +1         # from C of iteration i to A of iteration i + 1
+    /// B -> B +1
+    /// iterations 10
+    /// prefix P Q        # P -> Q -> (the first iteration)
+    /// suffix S          # (the last iteration) -> S
+    /// noise 20
+    /// seed 7
+    /// ```
+    ///
+    /// Where a name occurs more than once in `loop`, its occurrences are told apart as `C.0`, `C.1`, ...
+    /// Arrows need no spaces around them (`D->A`), as in edge lists. `loop` must occur once and
+    /// `iterations` is required.
+    pub fn parse(text: &str) -> Result<GeneratorSpec, LoadError> {
+        let mut spec = GeneratorSpec::default();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut has_loop = false;
+        let mut has_iterations = false;
+
+        for (i, line) in text.lines().enumerate() {
+            let number = i + 1;
+            let syntax = |message: String| LoadError::Syntax {
+                line: number,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or("");
+            let spaced = line.replace("->", " -> ");
+            let words: Vec<&str> = spaced.split_whitespace().collect();
+            let count = |w: Option<&&str>| -> Result<u64, LoadError> {
+                w.and_then(|w| w.parse().ok())
+                    .ok_or_else(|| syntax(format!("expected a number in `{}`", line.trim())))
+            };
+
+            match words.as_slice() {
+                [] => {}
+                ["loop", ..] if has_loop => {
+                    return Err(syntax("a spec has only one `loop` line".to_owned()))
+                }
+                ["loop", names @ ..] => {
+                    has_loop = true;
+                    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+                    for name in names {
+                        let k = occurrences.entry(name).or_default();
+                        index.insert(format!("{}.{}", name, k), spec.template.nodes.len());
+                        if *k == 0 {
+                            index.insert(name.to_string(), spec.template.nodes.len());
+                        }
+                        *k += 1;
+                        spec.template.nodes.push(name.to_string());
+                    }
+                }
+                [p, "->", c, rest @ ..] => {
+                    let node = |n: &str| {
+                        index
+                            .get(n)
+                            .copied()
+                            .ok_or_else(|| syntax(format!("{} is not declared in `loop`", n)))
+                    };
+                    let (p, c) = (node(p)?, node(c)?);
+                    match rest {
+                        [] if p == c || reaches(&spec.template.intra, c, p) => {
+                            return Err(syntax(format!(
+                                "`{}` closes a cycle within an iteration",
+                                line.trim()
+                            )))
+                        }
+                        [] => spec.template.intra.push((p, c)),
+                        [lag] if lag.starts_with('+') => {
+                            let lag =
+                                lag[1..]
+                                    .parse()
+                                    .ok()
+                                    .filter(|lag| *lag > 0)
+                                    .ok_or_else(|| {
+                                        syntax(format!("bad lag {} (expected +1 or more)", lag))
+                                    })?;
+                            spec.template.inter.push((p, c, lag));
+                        }
+                        _ => return Err(syntax(format!("bad edge `{}`", line.trim()))),
+                    }
+                }
+                ["iterations", n] => {
+                    spec.iterations = count(Some(n))? as usize;
+                    has_iterations = true;
+                }
+                ["prefix", names @ ..] => {
+                    spec.prefix = names.iter().map(|n| n.to_string()).collect()
+                }
+                ["suffix", names @ ..] => {
+                    spec.suffix = names.iter().map(|n| n.to_string()).collect()
+                }
+                ["noise", n] => spec.noise = count(Some(n))? as usize,
+                ["seed", n] => spec.seed = count(Some(n))?,
+                _ => return Err(syntax(format!("unknown directive `{}`", line.trim()))),
+            }
+        }
+
+        if !has_iterations {
+            return Err(LoadError::Syntax {
+                line: text.lines().count(),
+                message: "missing `iterations`".to_owned(),
+            });
+        }
+        Ok(spec)
+    }
+
+    /// Builds the graph described by the spec.
+    pub fn generate(&self) -> Generated {
+        self.generate_into(Graph::new())
+    }
+
+    /// Builds the graph described by the spec in `graph` (e.g. one created with `Graph::with_normalizer`).
+    pub fn generate_into(&self, mut graph: Graph) -> Generated {
+        let t = &self.template;
+
+        let prefix: Vec<RNode> = self
+            .prefix
+            .iter()
+            .map(|name| graph.add(Node::new(name.clone())))
+            .collect();
+        for w in prefix.windows(2) {
+            w[1].add(&w[0]);
+        }
+
+        let mut iterations: Vec<Vec<RNode>> = vec![];
+        for i in 0..self.iterations {
+            let it: Vec<RNode> = t
+                .nodes
+                .iter()
+                .map(|name| graph.add(Node::new(name.clone())))
+                .collect();
+
+            // parents are attached in template order so that parent lists line up across iterations
+            for (j, n) in it.iter().enumerate() {
+                if i == 0 {
+                    if let Some(p) = prefix.last() {
+                        let has_parent = t.intra.iter().any(|(_, c)| *c == j);
+                        if !has_parent {
+                            n.add(p);
+                        }
+                    }
+                }
+                for (p, _) in t.intra.iter().filter(|(_, c)| *c == j) {
+                    n.add(&it[*p]);
+                }
+                for (p, _, lag) in t.inter.iter().filter(|(_, c, _)| *c == j) {
+                    if let Some(earlier) = i.checked_sub(*lag as usize) {
+                        n.add(&iterations[earlier][*p]);
+                    }
+                }
+            }
+            iterations.push(it);
+        }
+
+        let suffix: Vec<RNode> = self
+            .suffix
+            .iter()
+            .map(|name| graph.add(Node::new(name.clone())))
+            .collect();
+        if let (Some(first), Some(last)) = (suffix.first(), iterations.last()) {
+            for n in last.iter() {
+                first.add(n);
+            }
+        }
+        for w in suffix.windows(2) {
+            w[1].add(&w[0]);
+        }
+
+        let mut rng = Rng::new(self.seed);
+        let mut noise: Vec<RNode> = vec![];
+        for _ in 0..self.noise {
+            if graph.sorted.is_empty() {
+                break;
+            }
+            let name = NOISE_NAMES[rng.below(NOISE_NAMES.len())];
+            let parent = graph.sorted[rng.below(graph.sorted.len())].clone();
+            let n = graph.add(Node::new(name.to_owned()));
+            n.add(&parent);
+            noise.push(n);
+        }
+
+        graph.sorted.sort();
+        Generated {
+            graph,
+            iterations,
+            prefix,
+            suffix,
+            noise,
+        }
+    }
+}
+
+/// Reads a generator spec and builds its graph into `graph`, see `GeneratorSpec::parse`.
+pub fn parse_into(text: &str, graph: Graph) -> Result<Graph, LoadError> {
+    let spec = GeneratorSpec::parse(text)?;
+    load::finish(spec.generate_into(graph).graph)
+}
@@ -1,9 +1,13 @@
 use std::cell::{Ref, RefCell, RefMut};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
+use crate::normalize::Normalizer;
+use crate::symbol::{Symbol, SymbolTable};
+
 #[derive(Debug, Clone)]
 pub struct RNode(Rc<RefCell<Node>>);
 
@@ -15,6 +19,125 @@ impl RNode {
         n.borrow_mut().children.push(self.clone());
     }
 
+    /// Like `add`, additionally recording why `self` depends on `n`.
+    pub fn add_labeled(&self, n: &RNode, label: EdgeLabel) {
+        self.add(n);
+        self.set_edge_label(n, label);
+    }
+
+    /// Labels the (existing) edge `n -> self`, replacing any previous label.
+    pub fn set_edge_label(&self, n: &RNode, label: EdgeLabel) {
+        let mut node = self.borrow_mut();
+        node.edge_labels.retain(|(p, _)| !p.ptr_eq(n));
+        node.edge_labels.push((n.clone(), label));
+    }
+
+    /// Returns the label of the edge `n -> self`, if it has one.
+    pub fn get_edge_label(&self, n: &RNode) -> Option<EdgeLabel> {
+        self.borrow()
+            .edge_labels
+            .iter()
+            .find(|(p, _)| p.ptr_eq(n))
+            .map(|(_, l)| l.clone())
+    }
+
+    /// Like `add`, but refuses edges that would close a cycle
+    /// (on which `add` would recurse forever while updating depths).
+    pub fn try_add_edge(&self, n: &RNode) -> Result<(), CycleError> {
+        if let Some(path) = self.path_to_descendant(n) {
+            return Err(CycleError {
+                parent: n.to_string(),
+                child: self.to_string(),
+                path,
+            });
+        }
+        self.add(n);
+        Ok(())
+    }
+
+    /// Removes the edge `n -> self`, returning false if there was none.
+    /// Depths of `n` and its ancestors are lowered where the edge was the one holding them up.
+    pub fn remove_edge(&self, n: &RNode) -> bool {
+        let Some(i) = self.borrow().parents.iter().position(|p| p.ptr_eq(n)) else {
+            return false;
+        };
+        self.borrow_mut().parents.remove(i);
+        if !self.borrow().parents.iter().any(|p| p.ptr_eq(n)) {
+            self.borrow_mut().edge_labels.retain(|(p, _)| !p.ptr_eq(n));
+        }
+        let j = n
+            .borrow()
+            .children
+            .iter()
+            .position(|c| c.ptr_eq(self))
+            .expect("edges are recorded on both ends");
+        n.borrow_mut().children.remove(j);
+        n.refresh_depth();
+        true
+    }
+
+    // recomputes depth from the children and propagates any change to the ancestors
+    fn refresh_depth(&self) {
+        let mut todo = vec![self.clone()];
+        while let Some(n) = todo.pop() {
+            let depth = n
+                .borrow()
+                .children
+                .iter()
+                .map(|c| c.get_depth() + 1)
+                .max()
+                .unwrap_or(0);
+            if depth != n.get_depth() {
+                n.borrow_mut().depth = depth;
+                todo.extend(n.get_parents());
+            }
+        }
+    }
+
+    // shortest path (in causal order) from self down to n, if n is self or one of its descendants
+    #[allow(clippy::mutable_key_type)]
+    fn path_to_descendant(&self, n: &RNode) -> Option<Vec<String>> {
+        if self.ptr_eq(n) {
+            return Some(vec![self.to_string()]);
+        }
+        // depth strictly decreases along child edges
+        if n.get_depth() >= self.get_depth() {
+            return None;
+        }
+
+        let mut preds: HashMap<RNode, RNode> = HashMap::new();
+        let mut frontier = VecDeque::from([self.clone()]);
+        while let Some(m) = frontier.pop_front() {
+            for c in m.borrow().children.iter() {
+                if c.get_depth() < n.get_depth() || preds.contains_key(c) {
+                    continue;
+                }
+                preds.insert(c.clone(), m.clone());
+                if c.ptr_eq(n) {
+                    let mut path = vec![c.to_string()];
+                    let mut cur = c.clone();
+                    while let Some(p) = preds.get(&cur) {
+                        path.push(p.to_string());
+                        if p.ptr_eq(self) {
+                            break;
+                        }
+                        cur = p.clone();
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                frontier.push_back(c.clone());
+            }
+        }
+        None
+    }
+
+    /// True if both handles refer to the very same node (as opposed to `==`,
+    /// which compares name and id and so cannot tell apart nodes of different graphs).
+    pub fn ptr_eq(&self, other: &RNode) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
     fn borrow_mut(&self) -> RefMut<'_, Node> {
         self.0.borrow_mut()
     }
@@ -32,7 +155,11 @@ impl RNode {
     }
 
     pub fn get_name(&self) -> String {
-        self.borrow().name.clone()
+        self.borrow().name.to_string()
+    }
+
+    pub fn get_symbol(&self) -> Symbol {
+        self.borrow().symbol
     }
 
     pub fn get_id(&self) -> u32 {
@@ -43,6 +170,10 @@ impl RNode {
         self.borrow().depth
     }
 
+    pub fn get_attributes(&self) -> Option<Attributes> {
+        self.borrow().attributes.as_deref().cloned()
+    }
+
     pub fn get_iteration(&self) -> Option<u32> {
         self.borrow().iteration
     }
@@ -54,18 +185,33 @@ impl RNode {
 
 impl Display for RNode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.borrow().to_string())
+        write!(f, "{}", self.borrow())
     }
 }
 
+// nodes of one graph share the allocation of their name, so equal names are mostly found by pointer
+fn same_name(a: &Node, b: &Node) -> bool {
+    Rc::ptr_eq(&a.name, &b.name) || a.name == b.name
+}
+
 impl PartialEq for RNode {
     fn eq(&self, other: &Self) -> bool {
-        self.borrow().name == other.borrow().name && self.borrow().id == other.borrow().id
+        let (a, b) = (self.borrow(), other.borrow());
+        a.id == b.id && same_name(&a, &b)
     }
 }
 
 impl Eq for RNode {}
 
+// names and ids do not change once a node is in a graph, so nodes are fine as keys of maps,
+// although clippy's `mutable_key_type` cannot tell; items that key maps by nodes allow it
+impl Hash for RNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.borrow().name.hash(state);
+        self.borrow().id.hash(state);
+    }
+}
+
 impl PartialOrd for RNode {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -78,18 +224,26 @@ impl Ord for RNode {
         if ord != Ordering::Equal {
             return ord;
         }
-        let ord = self.borrow().name.cmp(&other.borrow().name);
-        if ord != Ordering::Equal {
-            return ord;
+        let (a, b) = (self.borrow(), other.borrow());
+        if !same_name(&a, &b) {
+            let ord = a.name.cmp(&b.name);
+            if ord != Ordering::Equal {
+                return ord;
+            }
         }
-        self.borrow().id.cmp(&other.borrow().id)
+        a.id.cmp(&b.id)
     }
 }
 
-#[derive(Debug)]
+/// Nodes compare (and hash) by their name and id, also across graphs;
+/// the nodes of a quantifier share its interned name, which makes comparing them cheap.
+#[derive(Debug, Default)]
 pub struct Graph {
     pub nodes: HashMap<String, Vec<RNode>>,
     pub sorted: Vec<RNode>,
+    pub symbols: SymbolTable,
+    /// Applied to the name of every node added, see `with_normalizer`.
+    pub normalizer: Option<Normalizer>,
 }
 
 impl Graph {
@@ -97,16 +251,29 @@ impl Graph {
         Graph {
             nodes: HashMap::new(),
             sorted: vec![],
+            symbols: SymbolTable::new(),
+            normalizer: None,
+        }
+    }
+
+    /// A graph that groups nodes by their normalized quantifier name.
+    /// The name as found in the trace is kept as the node's quantifier id.
+    pub fn with_normalizer(normalizer: Normalizer) -> Graph {
+        Graph {
+            normalizer: Some(normalizer),
+            ..Graph::new()
         }
     }
 
     pub fn add(&mut self, n: Node) -> RNode {
-        let n = RNode(Rc::new(RefCell::new(n)));
-        if let Some(vec) = self.nodes.get_mut(&n.get_name()) {
-            n.borrow_mut().id = vec.len() as u32;
+        let n = self.intern(n);
+        let name = n.borrow().name.clone();
+        if let Some(vec) = self.nodes.get_mut(&*name) {
+            // not vec.len(), ids may have gaps after removals
+            n.borrow_mut().id = vec.last().map_or(0, |m| m.get_id() + 1);
             vec.push(n.clone());
         } else {
-            self.nodes.insert(n.get_name(), vec![n.clone()]);
+            self.nodes.insert(name.to_string(), vec![n.clone()]);
         }
 
         self.sorted.push(n.clone());
@@ -114,23 +281,332 @@ impl Graph {
         n
     }
 
+    /// Like `add`, but gives the node `id` instead of the next free id,
+    /// e.g. to keep the ids of a file. Returns `None` if the name already has a node with `id`.
+    pub fn add_with_id(&mut self, n: Node, id: u32) -> Option<RNode> {
+        let n = self.intern(n);
+        n.borrow_mut().id = id;
+        let name = n.borrow().name.clone();
+        let vec = self.nodes.entry(name.to_string()).or_default();
+        // keep vec ordered by id, which `add` relies on
+        let i = vec.partition_point(|m| m.get_id() < id);
+        if vec.get(i).is_some_and(|m| m.get_id() == id) {
+            return None;
+        }
+        vec.insert(i, n.clone());
+
+        self.sorted.push(n.clone());
+
+        Some(n)
+    }
+
+    // normalizes and interns the name of a node about to be added
+    fn intern(&mut self, mut n: Node) -> RNode {
+        if let Some(normalizer) = &self.normalizer {
+            let normalized = normalizer.normalize(&n.name);
+            if *normalized != *n.name {
+                let raw = std::mem::replace(&mut n.name, normalized.into());
+                let attributes = n.attributes.get_or_insert_with(Default::default);
+                attributes
+                    .quantifier_id
+                    .get_or_insert_with(|| raw.to_string());
+            }
+        }
+
+        let symbol = self.symbols.intern(&n.name);
+        n.name = self.symbols.resolve(symbol).clone();
+        n.symbol = symbol;
+
+        RNode(Rc::new(RefCell::new(n)))
+    }
+
+    /// Removes `n` and all of its edges from the graph, returning false if it was not in the graph.
+    /// Ids of the remaining nodes are left untouched (see `compact`).
+    pub fn remove(&mut self, n: &RNode) -> bool {
+        if !self.detach(n) {
+            return false;
+        }
+        self.sorted.retain(|m| !m.ptr_eq(n));
+        self.sorted.sort();
+        true
+    }
+
+    /// Removes every node for which `f` returns false, e.g. to prune a trace before analysis.
+    /// `f` sees the graph as it was before any removal.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&RNode) -> bool,
+    {
+        let (keep, removed): (Vec<RNode>, Vec<RNode>) =
+            self.sorted.iter().cloned().partition(|n| f(n));
+        for n in removed.iter() {
+            self.detach(n);
+        }
+        self.sorted = keep;
+        self.sorted.sort();
+    }
+
+    // removes n from nodes and from its neighbours' edge lists, but not from sorted
+    fn detach(&mut self, n: &RNode) -> bool {
+        let name = n.get_name();
+        let Some(vec) = self.nodes.get_mut(&name) else {
+            return false;
+        };
+        let Some(i) = vec.iter().position(|m| m.ptr_eq(n)) else {
+            return false;
+        };
+        vec.remove(i);
+        if vec.is_empty() {
+            self.nodes.remove(&name);
+        }
+
+        for c in n.get_children() {
+            c.remove_edge(n);
+        }
+        for p in n.get_parents() {
+            n.remove_edge(&p);
+        }
+        true
+    }
+
+    /// Renumbers ids per name to be dense again (0, 1, 2, ...) after removals,
+    /// preserving their relative order.
+    /// Existing maps keyed by `RNode` are invalidated, since nodes compare by name and id.
+    pub fn compact(&mut self) {
+        for vec in self.nodes.values() {
+            for (i, n) in vec.iter().enumerate() {
+                n.borrow_mut().id = i as u32;
+            }
+        }
+        self.sorted.sort();
+    }
+
+    /// Looks up the node with the given quantifier name and id.
+    pub fn get(&self, name: &str, id: u32) -> Option<RNode> {
+        let vec = self.nodes.get(name)?;
+        match vec.get(id as usize) {
+            Some(n) if n.get_id() == id => Some(n.clone()),
+            _ => vec.iter().find(|n| n.get_id() == id).cloned(),
+        }
+    }
+
+    /// Computes a value for every node from the values of its parents, causes first
+    /// (`sorted` in reverse, so it expects `sorted` to be sorted). The value of a node is only
+    /// kept until all of its children have been visited, so memory is bounded by the widest cut
+    /// of the graph rather than by its size.
+    #[allow(clippy::mutable_key_type)]
+    pub fn fold_causes<T>(&self, mut f: impl FnMut(&RNode, &[&T]) -> T) {
+        let mut values: HashMap<RNode, T> = HashMap::new();
+        let mut remaining: HashMap<RNode, usize> = HashMap::new();
+        for n in self.sorted.iter().rev() {
+            let parents = n.get_parents();
+            let value = f(
+                n,
+                &parents
+                    .iter()
+                    .filter_map(|p| values.get(p))
+                    .collect::<Vec<_>>(),
+            );
+            for p in parents.iter() {
+                let left = remaining
+                    .entry(p.clone())
+                    .or_insert_with(|| p.borrow().children.len());
+                *left -= 1;
+                if *left == 0 {
+                    remaining.remove(p);
+                    values.remove(p);
+                }
+            }
+            if !n.borrow().children.is_empty() {
+                values.insert(n.clone(), value);
+            }
+        }
+    }
+
     pub fn contains(&self, n: &RNode) -> bool {
         if let Some(vec) = self.nodes.get(&n.get_name()) {
-            vec.contains(n)
+            // ids are normally the index into vec
+            vec.get(n.get_id() as usize).is_some_and(|m| m.ptr_eq(n))
+                || vec.iter().any(|m| m.ptr_eq(n))
         } else {
             false
         }
     }
 }
 
+/// Returned by `RNode::try_add_edge` when the edge `parent -> child` would close a cycle.
+/// `path` is the existing causal path from `child` back to `parent`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    pub parent: String,
+    pub child: String,
+    pub path: Vec<String>,
+}
+
+impl Display for CycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "edge {} -> {} would close the cycle {} -> {}",
+            self.parent,
+            self.child,
+            self.parent,
+            self.path.join(" -> ")
+        )
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+/// What the solver trace says about an instantiation beyond its quantifier name.
+/// Every field is optional since loaders only fill in what their format provides.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attributes {
+    /// Identifier of the quantifier as given by the solver (e.g. its `:qid`).
+    pub quantifier_id: Option<String>,
+    /// The trigger pattern that fired.
+    pub pattern: Option<String>,
+    /// Bound variables and the terms they were instantiated with.
+    pub bindings: Vec<(String, String)>,
+    /// Instantiation generation as reported by the solver.
+    pub generation: Option<u32>,
+    /// Position of the instantiation in the trace.
+    pub timestamp: Option<u64>,
+    /// Where the quantifier comes from in the user's source.
+    pub location: Option<String>,
+}
+
+impl Attributes {
+    pub fn is_empty(&self) -> bool {
+        self == &Attributes::default()
+    }
+}
+
+impl Display for Attributes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields = vec![];
+        if let Some(qid) = &self.quantifier_id {
+            fields.push(format!("qid: {}", qid));
+        }
+        if let Some(pattern) = &self.pattern {
+            fields.push(format!("pattern: {}", pattern));
+        }
+        if !self.bindings.is_empty() {
+            let bindings: Vec<String> = self
+                .bindings
+                .iter()
+                .map(|(x, t)| format!("{} := {}", x, t))
+                .collect();
+            fields.push(format!("bindings: [{}]", bindings.join(", ")));
+        }
+        if let Some(generation) = self.generation {
+            fields.push(format!("generation: {}", generation));
+        }
+        if let Some(timestamp) = self.timestamp {
+            fields.push(format!("timestamp: {}", timestamp));
+        }
+        if let Some(location) = &self.location {
+            fields.push(format!("location: {}", location));
+        }
+        write!(f, "{{{}}}", fields.join(", "))
+    }
+}
+
+/// How an instantiation came to depend on one of its parents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyKind {
+    /// The parent produced a term that matched the child's trigger.
+    Trigger,
+    /// The parent produced an equality used to match the child's trigger.
+    Equality,
+}
+
+/// Explains an edge `parent -> child`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeLabel {
+    pub kind: DependencyKind,
+    /// The term produced by the parent that the child's trigger matched.
+    pub term: Option<String>,
+    /// The equalities the match went through (E-matching modulo equalities), in order.
+    pub equalities: Vec<String>,
+}
+
+impl EdgeLabel {
+    pub fn trigger(term: String) -> EdgeLabel {
+        EdgeLabel {
+            kind: DependencyKind::Trigger,
+            term: Some(term),
+            equalities: vec![],
+        }
+    }
+
+    pub fn equality(equalities: Vec<String>) -> EdgeLabel {
+        EdgeLabel {
+            kind: DependencyKind::Equality,
+            term: None,
+            equalities,
+        }
+    }
+
+    /// Describes the dependency in words, e.g. "G2 was triggered by term f(x1) produced by A1".
+    pub fn explain(&self, parent: &RNode, child: &RNode) -> String {
+        let mut s = match (self.kind, &self.term) {
+            (DependencyKind::Trigger, Some(t)) => {
+                format!(
+                    "{} was triggered by term {} produced by {}",
+                    child, t, parent
+                )
+            }
+            (DependencyKind::Trigger, None) => {
+                format!("{} was triggered by a term produced by {}", child, parent)
+            }
+            (DependencyKind::Equality, Some(t)) => {
+                format!(
+                    "{} matched term {} using an equality from {}",
+                    child, t, parent
+                )
+            }
+            (DependencyKind::Equality, None) => {
+                format!("{} matched using an equality from {}", child, parent)
+            }
+        };
+        if !self.equalities.is_empty() {
+            s.push_str(&format!(" (modulo {})", self.equalities.join(", ")));
+        }
+        s
+    }
+}
+
+impl Display for EdgeLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            DependencyKind::Trigger => write!(f, "trigger")?,
+            DependencyKind::Equality => write!(f, "equality")?,
+        }
+        if let Some(term) = &self.term {
+            write!(f, " {}", term)?;
+        }
+        if !self.equalities.is_empty() {
+            write!(f, " [{}]", self.equalities.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct Node {
-    pub name: String,
+    /// Shared with every other node of the same quantifier once added to a `Graph`.
+    pub name: Rc<str>,
+    /// `name` interned in the graph's symbol table, used for all comparisons.
+    pub symbol: Symbol,
     pub id: u32,
     pub depth: u32,
     pub iteration: Option<u32>,
+    pub attributes: Option<Box<Attributes>>,
     pub children: Vec<RNode>,
     pub parents: Vec<RNode>,
+    /// Labels of (some of) the edges from `parents`, keyed by parent.
+    pub edge_labels: Vec<(RNode, EdgeLabel)>,
 }
 
 impl Display for Node {
@@ -139,35 +615,40 @@ impl Display for Node {
     }
 }
 
-fn max(x: u32, y: u32) -> u32 {
-    if x >= y {
-        x
-    } else {
-        y
-    }
-}
-
 impl Node {
     pub fn new(name: String) -> Node {
         Node {
-            name,
+            name: name.into(),
+            symbol: Symbol::UNINTERNED,
             id: 0,
             depth: 0,
             iteration: None,
+            attributes: None,
             children: vec![],
             parents: vec![],
+            edge_labels: vec![],
         }
     }
 
+    pub fn with_attributes(mut self, attributes: Attributes) -> Node {
+        self.attributes = (!attributes.is_empty()).then(|| Box::new(attributes));
+        self
+    }
+
     pub fn update_depth(&mut self) {
-        for p in self.parents.iter() {
+        // with an explicit stack, long chains of causes do not overflow the call stack
+        let mut stack: Vec<(RNode, u32)> = self
+            .parents
+            .iter()
+            .map(|p| (p.clone(), self.depth + 1))
+            .collect();
+        while let Some((p, depth)) = stack.pop() {
             let mut p = p.borrow_mut();
-            let d = p.depth;
-            p.depth = max(d, self.depth + 1);
-        }
-
-        for p in self.parents.iter() {
-            p.borrow_mut().update_depth();
+            // the ancestors of a parent that is deep enough already are, too
+            if p.depth < depth {
+                p.depth = depth;
+                stack.extend(p.parents.iter().map(|q| (q.clone(), depth + 1)));
+            }
         }
     }
 }
@@ -0,0 +1,132 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::dot;
+use crate::edgelist;
+use crate::generator;
+use crate::graph::*;
+use crate::normalize::Normalizer;
+use crate::validate::Violation;
+
+/// Why a graph could not be read.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    /// The input is not in the expected format.
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    /// The input describes a cyclic graph.
+    Cycle(CycleError),
+    /// The input parsed but the resulting graph is not well formed.
+    Invalid(Vec<Violation>),
+    /// The file extension does not name a supported format.
+    UnknownFormat(String),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            LoadError::Cycle(e) => write!(f, "{}", e),
+            LoadError::Invalid(violations) => {
+                write!(f, "malformed graph:")?;
+                for v in violations.iter() {
+                    write!(f, "\n  {}", v)?;
+                }
+                Ok(())
+            }
+            LoadError::UnknownFormat(ext) => write!(f, "unknown graph format `{}`", ext),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<CycleError> for LoadError {
+    fn from(e: CycleError) -> Self {
+        LoadError::Cycle(e)
+    }
+}
+
+fn syntax(line: usize, message: String) -> LoadError {
+    LoadError::Syntax { line, message }
+}
+
+// a number that fits the field `k`, e.g. a `u32` generation
+fn number<T: std::str::FromStr>(k: &str, v: &str, line: usize) -> Result<T, LoadError> {
+    v.parse()
+        .map_err(|_| syntax(line, format!("{} expects a number, not {}", k, v)))
+}
+
+/// Reads the node attribute `k=v` into `a`, as every loader spells them: `qid`, `pattern`,
+/// `bind.<var>`, `generation`, `timestamp` and `location`. Returns whether `k` is one of them.
+pub fn node_attribute(
+    a: &mut Attributes,
+    k: &str,
+    v: &str,
+    line: usize,
+) -> Result<bool, LoadError> {
+    match k {
+        "qid" => a.quantifier_id = Some(v.to_owned()),
+        "pattern" => a.pattern = Some(v.to_owned()),
+        "generation" => a.generation = Some(number(k, v, line)?),
+        "timestamp" => a.timestamp = Some(number(k, v, line)?),
+        "location" => a.location = Some(v.to_owned()),
+        _ => match k.strip_prefix("bind.") {
+            Some(x) => a.bindings.push((x.to_owned(), v.to_owned())),
+            None => return Ok(false),
+        },
+    }
+    Ok(true)
+}
+
+/// Reads the value of the edge attribute `kind`.
+pub fn dependency_kind(v: &str, line: usize) -> Result<DependencyKind, LoadError> {
+    match v {
+        "trigger" => Ok(DependencyKind::Trigger),
+        "equality" => Ok(DependencyKind::Equality),
+        _ => Err(syntax(
+            line,
+            format!("unknown kind {} (expected trigger or equality)", v),
+        )),
+    }
+}
+
+/// Sorts a freshly loaded graph and checks it is well formed before it is handed to any analysis.
+pub fn finish(mut graph: Graph) -> Result<Graph, LoadError> {
+    graph.sorted.sort();
+    let violations = graph.validate();
+    if violations.is_empty() {
+        Ok(graph)
+    } else {
+        Err(LoadError::Invalid(violations))
+    }
+}
+
+/// Reads a graph from a file, choosing the format by extension.
+pub fn from_file(path: &Path, normalizer: Option<Normalizer>) -> Result<Graph, LoadError> {
+    let text = std::fs::read_to_string(path)?;
+    let graph = match normalizer {
+        Some(n) => Graph::with_normalizer(n),
+        None => Graph::new(),
+    };
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "dot" | "gv" => dot::parse_into(&text, graph),
+        "txt" | "edges" => edgelist::parse_into(&text, graph),
+        "loop" => generator::parse_into(&text, graph),
+        _ => Err(LoadError::UnknownFormat(ext)),
+    }
+}
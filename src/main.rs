@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 use std::iter::zip;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub mod graph;
 use graph::*;
@@ -7,7 +8,52 @@ use graph::*;
 pub mod frontier;
 use frontier::*;
 
-fn find_ith_progenitor(s: &RNode, i: usize) -> Option<RNode> {
+pub mod symbol;
+
+pub mod normalize;
+
+pub mod dot;
+pub mod edgelist;
+pub mod generator;
+pub mod load;
+#[cfg(test)]
+mod oracle;
+pub mod pairing;
+pub mod random;
+
+mod cli;
+
+pub mod slice;
+
+pub mod severity;
+pub mod stats;
+pub mod template;
+pub mod unroll;
+
+pub mod validate;
+
+pub mod bench;
+pub mod candidates;
+pub mod diff;
+pub mod fix;
+pub mod report;
+
+#[cfg(feature = "count-alloc")]
+#[global_allocator]
+static ALLOCATOR: bench::CountingAlloc = bench::CountingAlloc;
+
+/// Whether the search prints what it is doing (`mlg search --trace`).
+pub static TRACE: AtomicBool = AtomicBool::new(false);
+
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if TRACE.load(Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub fn find_ith_progenitor(s: &RNode, i: usize) -> Option<RNode> {
     if i < 1 {
         return None;
     }
@@ -17,7 +63,7 @@ fn find_ith_progenitor(s: &RNode, i: usize) -> Option<RNode> {
     let mut nodes: MinSet<RNode> = MinSet::new();
 
     let s_ref = s.borrow();
-    println!("{}", s);
+    trace!("{}", s);
 
     for n in &s_ref.parents {
         frontier.push(n.clone());
@@ -28,11 +74,11 @@ fn find_ith_progenitor(s: &RNode, i: usize) -> Option<RNode> {
         nodes.push(n.clone());
         let n_ref = n.borrow();
 
-        println!("{}", n);
+        trace!("{}", n);
 
-        if n_ref.name == s_ref.name && i == 1 {
+        if n_ref.symbol == s_ref.symbol && i == 1 {
             return Some(n.clone());
-        } else if n_ref.name == s_ref.name {
+        } else if n_ref.symbol == s_ref.symbol {
             // i > 1
             i -= 1;
         }
@@ -47,49 +93,71 @@ fn find_ith_progenitor(s: &RNode, i: usize) -> Option<RNode> {
     None
 }
 
-fn reset_iteration_vals(n: &RNode) {
-    if n.get_iteration() != None {
+thread_local! {
+    // the nodes the last search gave an iteration, see `reset_iteration_vals`
+    static LABELLED: std::cell::RefCell<Vec<RNode>> = const { std::cell::RefCell::new(vec![]) };
+}
+
+// puts `n` in iteration `i` and remembers it for the next `reset_iteration_vals`
+fn label(n: &RNode, i: u32) {
+    n.set_iteration(Some(i));
+    LABELLED.with(|l| l.borrow_mut().push(n.clone()));
+}
+
+/// Clears the iterations the previous search left on nodes.
+///
+/// This used to clear `s` and its ancestors up to the first one without an iteration. But a
+/// previous search, e.g. from another start node or with another `s_prime`, may have left labels
+/// above unlabeled nodes; those made the next search treat nodes as already paired (panicking on
+/// `expect`s or returning `None`). Every search records the nodes it labels, so clearing them costs
+/// no more than the previous search did, and the result of a search does not depend on the searches
+/// before it.
+pub fn reset_iteration_vals() {
+    for n in LABELLED.with(|l| std::mem::take(&mut *l.borrow_mut())) {
         n.set_iteration(None);
-        for c in n.borrow().parents.iter() {
-            reset_iteration_vals(c);
-        }
     }
 }
 
+// Both reassignments return `None` when an earlier reassignment already undid the pairs they would
+// undo (the frontier can still hold pairs pushed before); the attempt fails then.
+#[allow(clippy::mutable_key_type)]
 fn thrd_it_reassignment(
     n3: &RNode,
     pairs: &mut BTreeMap<RNode, RNode>,
     frontier: &mut MinHeap<(RNode, RNode)>,
-) {
+) -> Option<()> {
     let mut is_leaf_reassignment = true;
     for c in n3.borrow().children.iter() {
         if c.get_iteration() == Some(2) {
             is_leaf_reassignment = false;
-            thrd_it_reassignment(c, pairs, frontier);
+            thrd_it_reassignment(c, pairs, frontier)?;
         } else if c.get_iteration() == Some(0) {
-            let (c1, c2) = pairs
-                .remove_entry(c)
-                .expect("All iteration 0 nodes should be in pairs");
+            // all iteration 0 nodes are in pairs
+            let (c1, c2) = pairs.remove_entry(c)?;
             c1.set_iteration(None);
             c2.set_iteration(None);
             frontier.push((c1, c2));
         }
     }
 
-    let n2 = pairs
+    let Some(n2) = pairs
         .iter()
         .find(|(_, n_prime)| n_prime == &n3)
-        .expect("n2 predecessor of n3 should be in pairs")
-        .0
-        .clone();
-    let n1 = pairs
+        .map(|(n, _)| n.clone())
+    else {
+        trace!("3rd ItR: {} is no longer paired", n3);
+        return None;
+    };
+    let Some(n1) = pairs
         .iter()
         .find(|(_, n_prime)| n_prime == &&n2)
-        .expect("n1 predecessor of n2 should be in pairs")
-        .0
-        .clone();
+        .map(|(n, _)| n.clone())
+    else {
+        trace!("3rd ItR: {} is no longer paired", n2);
+        return None;
+    };
 
-    println!("3rd ItR: {}, {}, {}", n1, n2, n3);
+    trace!("3rd ItR: {}, {}, {}", n1, n2, n3);
 
     n1.set_iteration(None);
     n2.set_iteration(None);
@@ -101,9 +169,11 @@ fn thrd_it_reassignment(
     if is_leaf_reassignment {
         frontier.push((n2, n3.clone()));
     }
+    Some(())
 }
 
 //Recursively Explained by Start Nodes (RESN)
+#[allow(clippy::mutable_key_type)]
 fn is_resn(r: &RNode, start_pairs: &BTreeMap<RNode, RNode>) -> bool {
     r.get_iteration() == Some(1)
         && r.borrow()
@@ -112,11 +182,12 @@ fn is_resn(r: &RNode, start_pairs: &BTreeMap<RNode, RNode>) -> bool {
             .all(|p| start_pairs.contains_key(p) || is_resn(p, start_pairs))
 }
 
+#[allow(clippy::mutable_key_type)]
 fn resn_reassignment(
     n2: &RNode,
     pairs: &mut BTreeMap<RNode, RNode>,
     frontier: &mut MinHeap<(RNode, RNode)>,
-) {
+) -> Option<()> {
     let start_pairs: BTreeMap<RNode, RNode> = pairs
         .iter()
         .filter(|(n, _)| n.get_iteration() == Some(1))
@@ -130,33 +201,36 @@ fn resn_reassignment(
     while let Some(n) = to_check.pop() {
         for c in n.borrow().children.iter() {
             // TODO optimize conditions
-            let is_start_node = start_pairs.contains_key(&c);
+            let is_start_node = start_pairs.contains_key(c);
             let has_snd_it_child = c
                 .borrow()
                 .children
                 .iter()
                 .any(|g| g.get_iteration() == Some(1));
-            let c_is_resn = is_resn(&c, &start_pairs);
+            let c_is_resn = is_resn(c, &start_pairs);
             if is_start_node && has_snd_it_child {
                 continue;
             } else if has_snd_it_child && c_is_resn {
                 to_check.push(c.clone());
             } else if is_start_node && c_is_resn {
                 to_reassign.push(c.clone());
-                let c_prime = pairs.get(&c).expect("all start nodes should be paired");
+                // all start nodes are paired
+                let c_prime = pairs.get(c)?;
                 frontier.push((c.clone(), c_prime.clone()));
-                println!("RESN pushing ({},{}) to frontier", c, c_prime);
+                trace!("RESN pushing ({},{}) to frontier", c, c_prime);
             }
         }
     }
 
     while let Some(n2) = to_reassign.pop() {
-        let n1 = pairs
+        let Some(n1) = pairs
             .iter()
             .find(|(_, n_prime)| n_prime == &&n2)
-            .expect("n1 predecesor of n2 should be in pairs")
-            .0
-            .clone();
+            .map(|(n, _)| n.clone())
+        else {
+            trace!("RESN R: {} is no longer paired", n2);
+            return None;
+        };
 
         n1.set_iteration(None);
         n2.set_iteration(None);
@@ -166,9 +240,9 @@ fn resn_reassignment(
         if let Some(n3) = pairs.get(&n2).cloned() {
             n3.set_iteration(None);
             pairs.remove(&n2);
-            println!("RESN R: {}, {}, {}", n1, n2, n3);
+            trace!("RESN R: {}, {}, {}", n1, n2, n3);
         } else {
-            println!("RESN R: {}, {}", n1, n2);
+            trace!("RESN R: {}, {}", n1, n2);
         }
 
         for p in n2.borrow().parents.iter() {
@@ -180,69 +254,119 @@ fn resn_reassignment(
             }
         }
     }
+    Some(())
 }
 
-fn get_mlg(s: &RNode) -> Option<BTreeMap<RNode, RNode>> {
+pub fn get_mlg(s: &RNode) -> Option<BTreeMap<RNode, RNode>> {
     let max_num_attempts = 4;
+
+    for i in 1..=max_num_attempts {
+        trace!("Attempt #{i}");
+
+        let s_prime = find_ith_progenitor(s, i)?;
+        if let Some(pairs) = get_mlg_from(s, &s_prime) {
+            return Some(pairs);
+        }
+    }
+
+    None
+}
+
+/// One attempt of `get_mlg`: pairs `s` with the given progenitor `s_prime`, an ancestor of `s`
+/// as returned by `find_ith_progenitor`, and their ancestors.
+/// Leaves the iteration of every paired node set.
+#[allow(clippy::mutable_key_type)]
+pub fn get_mlg_from(s: &RNode, s_prime: &RNode) -> Option<BTreeMap<RNode, RNode>> {
     let max_num_node_actions = 100;
 
-    'next_attempt: for i in 1..=max_num_attempts {
-        println!("Attempt #{i}");
+    reset_iteration_vals();
 
-        reset_iteration_vals(s);
+    let mut frontier = MinHeap::new();
+    frontier.push((s.clone(), s_prime.clone()));
+    let mut pairs = BTreeMap::new();
 
-        let s_prime = match find_ith_progenitor(s, i) {
-            Some(s_prime) => s_prime,
-            None => return None,
-        };
+    let mut num_node_actions = 0;
+    'next_node: while let Some((n, n_prime)) = frontier.pop() {
+        num_node_actions += 1;
+        if num_node_actions > max_num_node_actions {
+            trace!("failed: more than {} node actions", max_num_node_actions);
+            return None;
+        }
+        // a cause shared by both iterations cannot be in both
+        if n.ptr_eq(&n_prime) {
+            trace!("{}, {} failed: paired with itself", n, n_prime);
+            return None;
+        }
+        // check n parents matches n_prime parents
+        if n.borrow().parents.len() != n_prime.borrow().parents.len()
+            || zip(n.borrow().parents.iter(), n_prime.borrow().parents.iter())
+                .any(|(p, p_prime)| p.get_symbol() != p_prime.get_symbol())
+        {
+            return None;
+        }
 
-        let mut frontier = MinHeap::new();
-        frontier.push((s.clone(), s_prime.clone()));
-        let mut pairs = BTreeMap::new();
-
-        let mut num_node_actions = 0;
-        'next_node: while !frontier.is_empty() || num_node_actions > max_num_node_actions {
-            num_node_actions += 1;
-            // get (n, n_prime) off frontier
-            let (n, n_prime) = frontier.pop().unwrap();
-            // check n parents matches n_prime parents
-            if n.borrow().parents.len() != n_prime.borrow().parents.len()
-                || zip(n.borrow().parents.iter(), n_prime.borrow().parents.iter())
-                    .any(|(p, p_prime)| p.borrow().name != p_prime.borrow().name)
-            {
-                continue 'next_attempt;
+        // n_prime was placed as the partner of another node, in an iteration that does not follow n's
+        if let (Some(i), Some(i_prime)) = (n.get_iteration(), n_prime.get_iteration()) {
+            if i + 1 != i_prime {
+                trace!(
+                    "{}, {} failed: in iterations {}, {}",
+                    n,
+                    n_prime,
+                    i,
+                    i_prime
+                );
+                return None;
             }
+        }
 
-            assert!(
-                n.get_iteration() == None
-                    || n_prime.get_iteration() == None
-                    || n.get_iteration().unwrap() + 1 == n_prime.get_iteration().unwrap()
-            );
-
-            match n.get_iteration() {
-                None => {
-                    if n_prime.get_iteration() != None {
-                        continue 'next_attempt;
-                    }
+        match n.get_iteration() {
+            None => {
+                if n_prime.get_iteration().is_some() {
+                    return None;
+                }
 
-                    n.set_iteration(Some(0));
-                    n_prime.set_iteration(Some(1));
+                label(&n, 0);
+                label(&n_prime, 1);
 
-                    println!("{}, {}, {:?} onto pairs", n, n_prime, n.get_iteration());
-                    for (p, p_prime) in zip(n.get_parents(), n_prime.get_parents()) {
-                        frontier.push((p, p_prime));
-                    }
-                    pairs.insert(n, n_prime);
+                trace!("{}, {}, {:?} onto pairs", n, n_prime, n.get_iteration());
+                for (p, p_prime) in zip(n.get_parents(), n_prime.get_parents()) {
+                    frontier.push((p, p_prime));
+                }
+                pairs.insert(n, n_prime);
+                continue 'next_node;
+            }
+            Some(0) => {
+                // n is in first iteration
+                // check that n, n_prime correspond
+                let x = pairs
+                    .get(&n)
+                    .expect("all Nodes assigned iteration 0 are in pairs");
+                if x == &n_prime {
+                    trace!(
+                        "{}, {}, {:?} already on pairs",
+                        n,
+                        n_prime,
+                        n.get_iteration()
+                    );
                     continue 'next_node;
+                } else {
+                    trace!(
+                        "{}, {}, {:?} failed: {}, {} already paired",
+                        n,
+                        n_prime,
+                        n.get_iteration(),
+                        n,
+                        x
+                    );
+                    return None;
                 }
-                Some(0) => {
-                    // n is in first iteration
-                    // check that n, n_prime correspond
-                    let x = pairs
-                        .get(&n)
-                        .expect("all Nodes assigned iteration 0 are in pairs");
+            }
+            Some(1) => {
+                // n is a start node
+                if let Some(x) = pairs.get(&n) {
+                    // if (n, x) has already been seen check that x == n_prime, continue
                     if x == &n_prime {
-                        println!(
+                        trace!(
                             "{}, {}, {:?} already on pairs",
                             n,
                             n_prime,
@@ -250,7 +374,7 @@ fn get_mlg(s: &RNode) -> Option<BTreeMap<RNode, RNode>> {
                         );
                         continue 'next_node;
                     } else {
-                        println!(
+                        trace!(
                             "{}, {}, {:?} failed: {}, {} already paired",
                             n,
                             n_prime,
@@ -258,59 +382,41 @@ fn get_mlg(s: &RNode) -> Option<BTreeMap<RNode, RNode>> {
                             n,
                             x
                         );
-                        continue 'next_attempt;
+                        return None;
                     }
-                }
-                Some(1) => {
-                    // n is a start node
-                    if let Some(x) = pairs.get(&n) {
-                        // if (n, x) has already been seen check that x == n_prime, continue
-                        if x == &n_prime {
-                            println!(
-                                "{}, {}, {:?} already on pairs",
-                                n,
-                                n_prime,
-                                n.get_iteration()
-                            );
-                            continue 'next_node;
-                        } else {
-                            println!(
-                                "{}, {}, {:?} failed: {}, {} already paired",
-                                n,
-                                n_prime,
-                                n.get_iteration(),
-                                n,
-                                x
-                            );
-                            continue 'next_attempt;
-                        }
-                    } else {
-                        // else mark n_prime as being in third iteration, try resn resassignment, continue
-                        n_prime.set_iteration(Some(2));
-                        println!("{}, {}, {:?} onto pairs", n, n_prime, n.get_iteration());
-                        pairs.insert(n.clone(), n_prime);
+                } else if n_prime.get_iteration().is_some() {
+                    trace!("{}, {} failed: {} already paired", n, n_prime, n_prime);
+                    return None;
+                } else {
+                    // else mark n_prime as being in third iteration, try resn resassignment, continue
+                    label(&n_prime, 2);
+                    trace!("{}, {}, {:?} onto pairs", n, n_prime, n.get_iteration());
+                    pairs.insert(n.clone(), n_prime);
 
-                        resn_reassignment(&n, &mut pairs, &mut frontier);
+                    resn_reassignment(&n, &mut pairs, &mut frontier)?;
 
-                        continue 'next_node;
-                    }
-                }
-                Some(2) => {
-                    // perform 3rd iteration reassignment
-                    thrd_it_reassignment(&n, &mut pairs, &mut frontier);
                     continue 'next_node;
                 }
-                _ => panic!("There shouldn't be any further assignments"),
             }
+            Some(2) => {
+                // perform 3rd iteration reassignment
+                thrd_it_reassignment(&n, &mut pairs, &mut frontier)?;
+                continue 'next_node;
+            }
+            _ => panic!("There shouldn't be any further assignments"),
         }
-
-        return Some(pairs);
     }
 
-    None
+    Some(pairs)
 }
 
-fn main() {}
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = cli::run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
 
 #[cfg(test)]
 pub mod tests {
@@ -427,15 +533,10 @@ pub mod tests {
         println!();
 
         for m in graph.sorted.iter().take(4) {
-            let n = find_ith_progenitor(&m, 2);
+            let n = find_ith_progenitor(m, 2);
 
             assert!(n.clone().is_some_and(|n| {
-                let x: u32;
-                if n.borrow().name != "C" {
-                    x = 2;
-                } else {
-                    x = 3;
-                }
+                let x: u32 = if n.get_name() != "C" { 2 } else { 3 };
 
                 let m = m.borrow();
                 let n = n.borrow();
@@ -454,30 +555,1119 @@ pub mod tests {
     }
 
     #[test]
+    pub fn test_descendants() {
+        let graph = test_graph_abcd();
+        let d5 = graph.nodes["D"][5].clone();
+
+        // A5 and B5, then every node of the earlier iterations
+        let all = slice::descendants(&d5, &slice::SliceOptions::default());
+        assert_eq!(all.len(), 22);
+
+        let near = slice::SliceOptions {
+            max_distance: Some(2),
+            quantifier: None,
+        };
+        let near: Vec<String> = slice::descendants(&d5, &near)
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        assert_eq!(near, vec!["C4", "B5", "A5"]);
+
+        let only_a = slice::SliceOptions {
+            max_distance: None,
+            quantifier: Some("A".to_owned()),
+        };
+        assert_eq!(slice::descendants(&d5, &only_a).len(), 6);
+    }
+
+    #[test]
+    pub fn test_forward_slice() {
+        let graph = test_graph_yorg();
+        let r1 = graph.nodes["R"][1].clone();
+        let slice = graph.forward_slice(&r1, &slice::SliceOptions::default());
+
+        // R1 -> O1 -> {G0, R0} -> O0
+        assert_eq!(slice.sorted.len(), 5);
+        assert_eq!(slice.sorted.last().unwrap().to_string(), "R1");
+        assert_eq!(slice.sorted.last().unwrap().get_depth(), 3);
+        assert_eq!(slice.nodes["R"].len(), 2);
+        assert!(!slice.nodes.contains_key("Y"));
+    }
+
+    #[test]
+    pub fn test_induced_subgraph() {
+        let graph = test_graph_layered();
+        let y3 = graph.nodes["Y"][3].clone();
+        let sub = graph.ancestor_subgraph(&y3, &slice::SliceOptions::default());
+
+        assert!(sub.contains_original(&y3));
+        for n in sub.graph.sorted.iter() {
+            let original = sub.original(n).unwrap();
+            assert_eq!(original.get_name(), n.get_name());
+            assert_eq!(sub.copy_of(&original).as_ref(), Some(n));
+            assert_eq!(original.get_parents().len(), n.get_parents().len());
+        }
+
+        // ids are dense per name
+        for (_, ns) in sub.graph.nodes.iter() {
+            for (i, n) in ns.iter().enumerate() {
+                assert_eq!(n.get_id(), i as u32);
+            }
+        }
+
+        // attributes travel with the copy
+        let mut graph = test_graph_layered();
+        let attributes = Attributes {
+            pattern: Some("{f(x)}".to_owned()),
+            bindings: vec![("x".to_owned(), "g(a)".to_owned())],
+            ..Default::default()
+        };
+        let y10 = graph.add(Node::new("Y".to_owned()).with_attributes(attributes.clone()));
+        graph.nodes["Y"][0].add(&y10);
+        let sub = graph.induced(&[y10, graph.nodes["Y"][0].clone()]);
+        assert_eq!(sub.graph.nodes["Y"][1].get_attributes(), Some(attributes));
+        assert_eq!(sub.graph.nodes["Y"][0].get_attributes(), None);
+    }
+
+    #[test]
+    pub fn test_edge_labels() {
+        let mut graph = Graph::new();
+        let a = graph.add(Node::new("A".to_owned()));
+        let g = graph.add(Node::new("G".to_owned()));
+        let h = graph.add(Node::new("H".to_owned()));
+        g.add_labeled(&a, EdgeLabel::trigger("f(x0)".to_owned()));
+        h.add(&a);
+
+        assert_eq!(h.get_edge_label(&a), None);
+        assert_eq!(
+            g.get_edge_label(&a).unwrap().explain(&a, &g),
+            "G0 was triggered by term f(x0) produced by A0"
+        );
+
+        let sub = graph.induced(&[a.clone(), g.clone()]);
+        let (a_copy, g_copy) = (sub.copy_of(&a).unwrap(), sub.copy_of(&g).unwrap());
+        assert_eq!(g_copy.get_edge_label(&a_copy), g.get_edge_label(&a));
+
+        assert!(g.remove_edge(&a));
+        assert_eq!(g.get_edge_label(&a), None);
+    }
+
+    #[test]
+    pub fn test_symbols() {
+        let graph = test_graph_layered();
+        assert_eq!(graph.symbols.len(), 5);
+
+        let c = graph.symbols.get("C").unwrap();
+        assert_eq!(&**graph.symbols.resolve(c), "C");
+        assert!(graph.nodes["C"].iter().all(|n| n.get_symbol() == c));
+        assert_ne!(graph.nodes["Y"][0].get_symbol(), c);
+        assert!(graph.symbols.get("A").is_none());
+
+        // symbols of different graphs do not decide whether nodes are equal, names do
+        let (first, name) = graph.symbols.iter().next().unwrap();
+        let mut other = Graph::new();
+        let z0 = other.add(Node::new("Z".to_owned()));
+        let c0 = other.add(Node::new("C".to_owned()));
+        assert_eq!(z0.get_symbol(), first);
+        assert!(z0 != graph.nodes[name][0]);
+        assert!(c0 == graph.nodes["C"][0]);
+        assert!(std::collections::HashSet::from([graph.nodes["C"][0].clone()]).contains(&c0));
+        assert!(!graph.contains(&c0));
+    }
+
+    #[test]
+    pub fn test_normalize() {
+        let config = "
+            # Boogie line numbers
+            rewrite ^(\\w+)\\.l[0-9]+$ => $1
+            strip @
+            alias prelude = Prelude
+        ";
+        let normalizer = normalize::Normalizer::parse(config).unwrap();
+        assert_eq!(normalizer.normalize("inv.l12"), "inv");
+        assert_eq!(normalizer.normalize("inv@3"), "inv");
+        assert_eq!(normalizer.normalize("inv@x"), "inv@x");
+        assert_eq!(normalizer.normalize("prelude@7"), "Prelude");
+        assert_eq!(
+            normalize::Normalizer::parse("strip @\nrewrite ( => x")
+                .unwrap_err()
+                .line,
+            2
+        );
+
+        let mut graph = Graph::with_normalizer(normalizer);
+        let a = graph.add(Node::new("inv@1".to_owned()));
+        let b = graph.add(Node::new("inv.l40".to_owned()));
+        let c = graph.add(Node::new("other".to_owned()));
+        assert_eq!(a.get_symbol(), b.get_symbol());
+        assert_eq!(graph.nodes["inv"].len(), 2);
+        assert_eq!(b.to_string(), "inv1");
+        assert_eq!(
+            b.get_attributes().unwrap().quantifier_id.as_deref(),
+            Some("inv.l40")
+        );
+        assert_eq!(c.get_attributes(), None);
+    }
+
+    #[test]
+    pub fn test_read_dot() {
+        let text = r#"
+            // the first two iterations of test_graph_path
+            digraph path {
+                node [shape=box];
+                A0 -> B0 [term="f(a0)"];
+                A1 -> A0;
+                B1 -> B0;
+                B1 -> A0 [kind=equality, equalities="b1 = a0"];
+                n7 [label="A2", qid="path.l3"];
+                n7 -> { A1 B1 }
+            }
+        "#;
+        let graph = dot::parse(text).unwrap();
+        assert_eq!(graph.sorted.len(), 5);
+        assert_eq!(graph.nodes["A"].len(), 3);
+
+        let (a0, b0, a2) = (
+            graph.get("A", 0).unwrap(),
+            graph.get("B", 0).unwrap(),
+            graph.get("A", 2).unwrap(),
+        );
+        assert_eq!(
+            b0.get_edge_label(&a0).unwrap().term.as_deref(),
+            Some("f(a0)")
+        );
+        assert_eq!(
+            a2.get_attributes().unwrap().quantifier_id.as_deref(),
+            Some("path.l3")
+        );
+        assert_eq!(a2.get_children().len(), 2);
+        assert_eq!(a2.get_depth(), 3);
+
+        // ids are kept as written, unnumbered and repeated ones come after them
+        let graph =
+            dot::parse("digraph { A3 -> A5; A5 -> A7; x [label=A]; n1 [label=A3] }").unwrap();
+        let ids: Vec<u32> = graph.nodes["A"].iter().map(|n| n.get_id()).collect();
+        assert_eq!(ids, [3, 5, 7, 8, 9]);
+        assert_eq!(
+            graph.get("A", 3).unwrap().get_children()[0].to_string(),
+            "A5"
+        );
+        assert!(graph.validate().is_empty());
+
+        assert!(matches!(
+            dot::parse("digraph { A0 -> B0; B0 -> A0 }"),
+            Err(load::LoadError::Cycle(_))
+        ));
+        assert!(matches!(
+            dot::parse("digraph {\n A0 -> \n}"),
+            Err(load::LoadError::Syntax { line: 3, .. })
+        ));
+        assert!(dot::parse("graph { A0 -- B0 }").is_err());
+
+        // attributes are read as in edge lists, and rejected with their line if malformed
+        let graph = dot::parse(
+            "digraph {\n A0 [\"bind.y\"=b \"bind.x\"=a generation=3 color=red]\n A0 -> B0 [kind=equality]\n}",
+        )
+        .unwrap();
+        let a = graph.get("A", 0).unwrap().get_attributes().unwrap();
+        assert_eq!(
+            a.bindings,
+            vec![
+                ("x".to_owned(), "a".to_owned()),
+                ("y".to_owned(), "b".to_owned())
+            ]
+        );
+        assert_eq!(a.generation, Some(3));
+        for text in [
+            "digraph {\n A0 -> B0\n A0 -> C0 [kind=trigered]\n}",
+            "digraph {\n A0 -> B0\n A0 [generation=5000000000]\n}",
+            "digraph {\n A0 -> B0\n B0 [timestamp=soon]\n}",
+        ] {
+            assert!(
+                matches!(
+                    dot::parse(text),
+                    Err(load::LoadError::Syntax { line: 3, .. })
+                ),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_edgelist() {
+        let text = "
+            # test_graph_path, first three iterations
+            A0 -> B1
+            B0 -> B1
+            A1 -> A0
+            A1 -> B2
+            B1 -> B2 [term=\"f(b1)\"]
+            A2 -> A1
+            \"my quantifier\"#0 [pattern={g(x)} bind.x=a2]
+            x1#0 -> B0 -> A0
+        ";
+        let graph = edgelist::parse(text).unwrap();
+        assert_eq!(graph.sorted.len(), 8);
+        let b1 = graph.get("B", 1).unwrap();
+        let names = |ns: Vec<RNode>| ns.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(names(b1.get_parents()), vec!["A0", "B0"]);
+        assert_eq!(
+            graph
+                .get("my quantifier", 0)
+                .unwrap()
+                .get_attributes()
+                .unwrap()
+                .bindings,
+            vec![("x".to_owned(), "a2".to_owned())]
+        );
+        assert!(graph.get("x1", 0).is_some());
+        // nodes as given on the command line
+        assert_eq!(edgelist::parse_node("B1").unwrap(), ("B".to_owned(), 1));
+        assert_eq!(edgelist::parse_node("x1#0").unwrap(), ("x1".to_owned(), 0));
+        assert_eq!(
+            edgelist::parse_node("\"my quantifier\"#0").unwrap(),
+            ("my quantifier".to_owned(), 0)
+        );
+        assert!(edgelist::parse_node("A").is_err() && edgelist::parse_node("A0 -> B0").is_err());
+
+        // round trip
+        let written = edgelist::write(&graph);
+        assert_eq!(
+            edgelist::write(&edgelist::parse(&written).unwrap()),
+            written
+        );
+        for graph in [test_graph_abcd(), test_graph_layered()] {
+            let copy = edgelist::parse(&edgelist::write(&graph)).unwrap();
+            assert_eq!(edgelist::write(&copy), edgelist::write(&graph));
+            for n in graph.sorted.iter() {
+                let m = copy.get(&n.get_name(), n.get_id()).unwrap();
+                assert_eq!(m.get_depth(), n.get_depth());
+                assert_eq!(names(m.get_parents()), names(n.get_parents()));
+            }
+        }
+
+        // ids as written, arrows without spaces
+        let graph = edgelist::parse("A3->A5 -> A7\n\"a->b\"#2 -> A3 [term=\"x -> y\"]").unwrap();
+        assert_eq!(names(graph.get("A", 5).unwrap().get_parents()), vec!["A3"]);
+        assert_eq!(graph.get("a->b", 2).unwrap().get_children().len(), 1);
+        let written = edgelist::write(&graph);
+        assert_eq!(
+            written,
+            "\"a->b\"#2 -> A3 [term=\"x -> y\"]\nA3 -> A5\nA5 -> A7\n"
+        );
+        assert_eq!(
+            edgelist::write(&edgelist::parse(&written).unwrap()),
+            written
+        );
+
+        assert!(matches!(
+            edgelist::parse("A0 -> B0\nB0 -> \n"),
+            Err(load::LoadError::Syntax { line: 2, .. })
+        ));
+        assert!(matches!(
+            edgelist::parse("A0 -> A0"),
+            Err(load::LoadError::Cycle(_))
+        ));
+        // a generation beyond u32 is an error, not a truncated number
+        assert!(matches!(
+            edgelist::parse("A0 [generation=5000000000]"),
+            Err(load::LoadError::Syntax { line: 1, .. })
+        ));
+        let graph = edgelist::parse("A0 [generation=7 timestamp=5000000000]").unwrap();
+        let a = graph.get("A", 0).unwrap().get_attributes().unwrap();
+        assert_eq!((a.generation, a.timestamp), (Some(7), Some(5000000000)));
+    }
+
+    // checks that `generated` is `fixture` with iterations numbered in causal order
+    fn assert_same_loop(fixture: &Graph, generated: &Graph, iterations: u32) {
+        assert_eq!(fixture.sorted.len(), generated.sorted.len());
+        let mirror = |n: &RNode| {
+            let per_iteration = fixture.nodes[&n.get_name()].len() as u32 / iterations;
+            let (i, j) = (n.get_id() / per_iteration, n.get_id() % per_iteration);
+            let id = (iterations - 1 - i) * per_iteration + j;
+            generated.get(&n.get_name(), id).unwrap()
+        };
+        for n in fixture.sorted.iter() {
+            let expected: Vec<String> = n
+                .get_parents()
+                .iter()
+                .map(|p| mirror(p).to_string())
+                .collect();
+            let actual: Vec<String> = mirror(n)
+                .get_parents()
+                .iter()
+                .map(|p| p.to_string())
+                .collect();
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    pub fn test_generator() {
+        let abcd = generator::GeneratorSpec::parse(
+            "
+            loop A B C D
+            D -> A
+            A -> B
+            C -> B
+            A -> C +1
+            B -> B +1
+            B -> D +1
+            iterations 10
+            ",
+        )
+        .unwrap();
+        let generated = abcd.generate();
+        assert_eq!(generated.iterations.len(), 10);
+        assert_same_loop(&test_graph_abcd(), &generated.graph, 10);
+
+        let layered = generator::GeneratorSpec::parse(
+            "
+            loop R B G C C C Y Y
+            B -> R
+            G -> R
+            C.0 -> B
+            C.1 -> C.0
+            C.2 -> C.1
+            Y.0 -> G
+            Y.1 -> Y.0
+            R -> Y.1 +1
+            R -> C.2 +1
+            iterations 10
+            ",
+        )
+        .unwrap();
+        assert_same_loop(&test_graph_layered(), &layered.generate().graph, 10);
+
+        let mut decorated = layered.clone();
+        decorated.prefix = vec!["P".to_owned(), "Q".to_owned()];
+        decorated.suffix = vec!["S".to_owned()];
+        decorated.noise = 15;
+        let generated = decorated.generate();
+        assert_eq!(generated.graph.sorted.len(), 80 + 3 + 15);
+        assert_eq!(generated.graph.validate(), vec![]);
+        assert_eq!(generated.suffix[0].get_parents().len(), 8);
+        // noise only ever causes more noise
+        assert!(generated.noise.iter().all(|n| n
+            .get_children()
+            .iter()
+            .all(|c| generator::NOISE_NAMES.contains(&c.get_name().as_str()))));
+        assert_eq!(
+            edgelist::write(&decorated.generate().graph),
+            edgelist::write(&generated.graph)
+        );
+
+        assert!(generator::GeneratorSpec::parse("loop A\nA -> B\niterations 2").is_err());
+        for bad in [
+            "loop A B\nA -> B\nB -> A\niterations 2",
+            "loop A\nA -> A\niterations 2",
+            "loop A\nA -> A +0\niterations 2",
+            "loop A C C\nloop B\nC.1 -> B\niterations 2",
+            "loop A\nA -> A +1",
+        ] {
+            assert!(
+                matches!(
+                    generator::GeneratorSpec::parse(bad),
+                    Err(load::LoadError::Syntax { .. })
+                ),
+                "{}",
+                bad
+            );
+        }
+        // arrows without spaces
+        assert_eq!(
+            generator::GeneratorSpec::parse("loop A B\nA->B\nB->A +1\niterations 2").unwrap(),
+            generator::GeneratorSpec::parse("loop A B\nA -> B\nB -> A +1\niterations 2").unwrap()
+        );
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    pub fn test_planted_loops() {
+        let spec = random::RandomSpec {
+            seed: 3,
+            loops: 2,
+            noise: 40,
+            interference: 0.1,
+            ..Default::default()
+        };
+        let planted = random::plant(&spec);
+        assert_eq!(planted.graph.validate(), vec![]);
+        assert_eq!(planted.noise.len(), 40);
+        for l in planted.loops.iter() {
+            assert_eq!(l.iterations.len(), spec.iterations);
+            let pairing = l.pairing();
+            assert_eq!(
+                pairing.len(),
+                (spec.iterations - 1) * l.template.nodes.len()
+            );
+            assert!(pairing.iter().all(|(n, p)| n.get_name() == p.get_name()));
+        }
+        // seeded
+        assert_eq!(
+            edgelist::write(&random::plant(&spec).graph),
+            edgelist::write(&planted.graph)
+        );
+
+        // what the search achieves on these seeds today; raise the bounds when it improves
+        let d = random::measure(&random::RandomSpec::default(), 20);
+        assert_eq!(d.loops, 20);
+        assert!(d.rate() >= 0.4, "{:?}", d);
+        assert!(d.wrong <= 1 && d.false_pairs <= 1, "{:?}", d);
+        assert_eq!(d.spurious, 0, "{:?}", d);
+    }
+
+    // A graph for property tests: `iterations` copies of `template`, then `extra` nodes,
+    // then `edges` between arbitrary nodes (by creation order, skipping those that would close a cycle).
+    // It shows as its edge list, so a shrunk counterexample can be saved as a .txt file and reloaded.
+    #[derive(Clone)]
+    struct Recipe {
+        template: generator::LoopSpec,
+        iterations: usize,
+        extra: Vec<usize>,
+        edges: Vec<(usize, usize)>,
+    }
+
+    const NAMES: [&str; 3] = ["A", "B", "C"];
+
+    impl Recipe {
+        fn build(&self) -> Graph {
+            let generated =
+                generator::GeneratorSpec::new(self.template.clone(), self.iterations).generate();
+            let mut graph = generated.graph;
+            let mut nodes: Vec<RNode> = generated.iterations.into_iter().flatten().collect();
+            for k in self.extra.iter() {
+                nodes.push(graph.add(Node::new(NAMES[*k].to_owned())));
+            }
+            for (p, c) in self.edges.iter() {
+                let (p, c) = (&nodes[p % nodes.len()], &nodes[c % nodes.len()]);
+                if !c.get_parents().iter().any(|q| q.ptr_eq(p)) {
+                    let _ = c.try_add_edge(p);
+                }
+            }
+            graph.sorted.sort();
+            graph
+        }
+    }
+
+    impl std::fmt::Debug for Recipe {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "\n{}", edgelist::write(&self.build()))
+        }
+    }
+
+    fn recipe() -> impl proptest::strategy::Strategy<Value = Recipe> {
+        use proptest::collection::vec;
+        use proptest::prelude::*;
+
+        (
+            vec(0..NAMES.len(), 1..4),
+            vec((0..4usize, 0..4usize), 0..4),
+            vec((0..4usize, 0..4usize, 1..3u32), 1..3),
+            1..6usize,
+            vec(0..NAMES.len(), 0..6),
+            vec((0..64usize, 0..64usize), 0..12),
+        )
+            .prop_map(|(names, intra, inter, iterations, extra, edges)| {
+                let len = names.len();
+                let mut template = generator::LoopSpec {
+                    nodes: names.iter().map(|k| NAMES[*k].to_owned()).collect(),
+                    ..Default::default()
+                };
+                // parents come first within an iteration
+                for (p, c) in intra {
+                    let (p, c) = (p % len, c % len);
+                    if p < c && !template.intra.contains(&(p, c)) {
+                        template.intra.push((p, c));
+                    }
+                }
+                for (p, c, lag) in inter {
+                    let edge = (p % len, c % len, lag);
+                    if !template.inter.contains(&edge) {
+                        template.inter.push(edge);
+                    }
+                }
+                Recipe {
+                    template,
+                    iterations,
+                    extra,
+                    edges,
+                }
+            })
+    }
+
+    proptest::proptest! {
+        // the same cases on every run; a failure prints its graph to turn into a test
+        #![proptest_config(proptest::test_runner::Config {
+            rng_seed: proptest::test_runner::RngSeed::Fixed(0x6d6c67),
+            failure_persistence: None,
+            ..Default::default()
+        })]
+
+        #[test]
+        fn prop_graph_invariants(recipe in recipe()) {
+            let graph = recipe.build();
+            proptest::prop_assert_eq!(graph.validate(), vec![]);
+            for n in graph.sorted.iter() {
+                let deepest = n.get_children().iter().map(|c| c.get_depth() + 1).max();
+                proptest::prop_assert_eq!(n.get_depth(), deepest.unwrap_or(0));
+            }
+        }
+
+        #[test]
+        #[allow(clippy::mutable_key_type)]
+        fn prop_mlg_invariants(recipe in recipe(), start in 0..4usize) {
+            let graph = recipe.build();
+            let s = &graph.sorted[start % graph.sorted.len()];
+            let Some(pairs) = get_mlg(s) else {
+                return Ok(());
+            };
+
+            let mut seen: HashMap<RNode, u32> = HashMap::new();
+            for (n, n_prime) in pairs.iter() {
+                let (i, i_prime) = (n.get_iteration(), n_prime.get_iteration());
+                proptest::prop_assert!(i.is_some() && i_prime == i.map(|i| i + 1), "({},{})", n, n_prime);
+                proptest::prop_assert_eq!(n.get_name(), n_prime.get_name());
+                // no node in two iterations
+                for (m, k) in [(n, i.unwrap()), (n_prime, i_prime.unwrap())] {
+                    proptest::prop_assert_eq!(*seen.entry(m.clone()).or_insert(k), k, "{}", m);
+                }
+                // the parents of iteration 0 are paired along; the loop starts in iteration 1
+                if i == Some(0) {
+                    for (p, p_prime) in zip(n.get_parents(), n_prime.get_parents()) {
+                        proptest::prop_assert!(pairs.get(&p) == Some(&p_prime), "({},{}) via ({},{})", p, p_prime, n, n_prime);
+                    }
+                }
+            }
+            // nothing is the partner of two nodes
+            let partners: std::collections::BTreeSet<&RNode> = pairs.values().collect();
+            proptest::prop_assert_eq!(partners.len(), pairs.len());
+        }
+    }
+
+    #[test]
+    pub fn test_bench_shapes() {
+        for shape in bench::SHAPES {
+            assert_eq!(bench::Shape::parse(&shape.to_string()), Some(shape));
+            let report = bench::run(shape, 100);
+            assert!(report.found, "{}", shape);
+            // memory is only known when allocations are counted
+            assert_eq!(
+                report.build.memory.is_some_and(|m| m > 0),
+                bench::COUNTING,
+                "{}",
+                shape
+            );
+        }
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    pub fn test_template() {
+        let graph = test_graph_abcd();
+        let pairs = get_mlg(&graph.sorted[0]).unwrap();
+        let t = template::LoopTemplate::from_pairs(&pairs);
+        assert_eq!(t.len(), 4);
+        assert_eq!(
+            t.to_string(),
+            "per iteration: A, B, C, D\nA → B\nC → B\nD → A\nA → C next iteration\nB → (B, D) next iteration\n"
+        );
+
+        // the same loop in a longer run of it
+        let generated = generator::GeneratorSpec::parse(
+            "loop A B C D\nD -> A\nA -> B\nC -> B\nA -> C +1\nB -> D +1\nB -> B +1\niterations 30",
+        )
+        .unwrap()
+        .generate()
+        .graph;
+        let longer =
+            get_mlg(&generated.sorted[0]).map(|pairs| template::LoopTemplate::from_pairs(&pairs));
+        assert_eq!(longer, Some(t));
+
+        let layered = test_graph_layered();
+        let pairs = get_mlg(&layered.sorted[0]).unwrap();
+        let t = template::LoopTemplate::from_pairs(&pairs);
+        assert_eq!(t.quantifiers["C"], 3);
+        assert_eq!(t.len(), 8);
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    pub fn test_unroll() {
+        let names = |nodes: &[RNode]| nodes.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        let graph = test_graph_abcd();
+        let pairs = get_mlg(&graph.sorted[0]).unwrap();
+        let unrolled = unroll::unroll(&pairs);
+        assert_eq!(unrolled.count(), 10);
+        // the newest instantiations cause the older ones here
+        assert_eq!(names(unrolled.first()), ["A9", "B9", "C9", "D9"]);
+        assert_eq!(names(unrolled.last()), ["A0", "B0", "C0", "D0"]);
+
+        let generated = generator::GeneratorSpec::parse(
+            "loop R B G C C C Y Y
+            B -> R
+            G -> R
+            C.0 -> B
+            C.1 -> C.0
+            C.2 -> C.1
+            Y.0 -> G
+            Y.1 -> Y.0
+            R -> Y.0 +1
+            R -> C.2 +1
+            prefix P
+            suffix S
+            iterations 40
+            noise 30",
+        )
+        .unwrap()
+        .generate();
+        let s = generated.iterations[20].iter().min().unwrap();
+        let pairs = get_mlg(s).unwrap();
+        let unrolled = unroll::unroll(&pairs);
+        assert_eq!(unrolled.count(), 40);
+        for (it, expected) in zip(unrolled.iterations.iter(), generated.iterations.iter()) {
+            let mut expected = expected.clone();
+            expected.sort_by_key(|n| (n.get_name(), n.get_id()));
+            // nodes of one quantifier may be in another order
+            let key = |nodes: &[RNode]| {
+                let mut names = names(nodes);
+                names.sort();
+                names
+            };
+            assert_eq!(key(it), key(&expected));
+        }
+    }
+
+    #[test]
+    pub fn test_rank() {
+        let big = generator::GeneratorSpec::parse("loop A B\nA -> B\nB -> A +1\niterations 20")
+            .unwrap()
+            .generate_into(Graph::new());
+        let small = generator::GeneratorSpec::parse("loop X\nX -> X +1\niterations 5\nnoise 10")
+            .unwrap()
+            .generate_into(big.graph);
+        let graph = small.graph;
+
+        let ranked = severity::rank(&graph, &severity::default_starts(&graph));
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].unrolled.count(), 20);
+        assert_eq!(ranked[0].template.len(), 2);
+        assert_eq!(ranked[1].unrolled.count(), 5);
+        assert!(ranked[0].attributable >= 40);
+        assert!(ranked[0].share > ranked[1].share);
+        // the same loop searched from two of its nodes
+        let twice = severity::rank(
+            &graph,
+            &[graph.nodes["A"][3].clone(), graph.nodes["B"][7].clone()],
+        );
+        assert_eq!(twice.len(), 1);
+    }
+
+    #[test]
+    pub fn test_stats() {
+        let graph = test_graph_abcd();
+        let stats = graph.stats();
+        assert_eq!(stats.nodes, 40);
+        assert_eq!(stats.edges, 3 * 10 + 3 * 9);
+        assert!(stats.quantifiers.iter().all(|(_, count)| *count == 10));
+        assert_eq!(stats.depths.values().sum::<usize>(), 40);
+        assert_eq!(stats.fan_in[&3], 9);
+        let depth = graph.sorted.last().unwrap().get_depth();
+        assert_eq!(stats.longest_chain.len() as u32, depth + 1);
+        assert!(stats
+            .longest_chain
+            .windows(2)
+            .all(|w| w[1].get_parents().iter().any(|p| p.ptr_eq(&w[0]))));
+        // every iteration but the first is caused by the previous one,
+        // but C of the second iteration only by A and D of the first
+        assert_eq!(
+            stats.self_ancestry,
+            [("A", 9), ("B", 9), ("D", 9), ("C", 8)].map(|(q, n)| (q.to_owned(), n))
+        );
+    }
+
+    #[test]
+    pub fn test_candidates() {
+        let graph = test_graph_abcd();
+        let best = candidates::candidates(&graph, 3);
+        assert_eq!(best.len(), 3);
+        let mut names: Vec<String> = best.iter().map(|c| c.node.get_name()).collect();
+        names.dedup();
+        assert_eq!(names.len(), 3);
+        assert!(best.windows(2).all(|w| w[0].score >= w[1].score));
+        // the last iteration is caused by all nine before it
+        assert_eq!(best[0].repetitions, 9);
+        for c in best.iter() {
+            assert!(get_mlg(&c.node).is_some(), "no loop from {}", c.node);
+        }
+
+        // nothing repeats on a path of distinct quantifiers
+        let mut graph = Graph::new();
+        let a = graph.add(Node::new("A".to_owned()));
+        let b = graph.add(Node::new("B".to_owned()));
+        b.add(&a);
+        graph.sorted.sort();
+        assert!(candidates::candidates(&graph, 3).is_empty());
+    }
+
+    #[test]
+    pub fn test_diff() {
+        use diff::{Change, LoopSummary};
+        let summaries = |graph: &Graph| -> Vec<LoopSummary> {
+            let starts: Vec<RNode> = candidates::candidates(graph, usize::MAX)
+                .into_iter()
+                .map(|c| c.node)
+                .collect();
+            let ranked = severity::rank(graph, &starts);
+            ranked.iter().map(LoopSummary::from).collect()
+        };
+        let before = summaries(&test_graph_abcd());
+        assert_eq!(before.len(), 1);
+        let after = summaries(
+            &generator::GeneratorSpec::parse(
+                "loop A B C D\nD -> A\nA -> B\nC -> B\nA -> C +1\nB -> D +1\nB -> B +1\niterations 4",
+            )
+            .unwrap()
+            .generate()
+            .graph,
+        );
+        let changes = diff::diff(&before, &after);
+        assert!(
+            matches!(&changes[..], [Change::Repetitions { before, after }]
+                if before.iterations == 10 && after.iterations == 4),
+            "{:?}",
+            changes
+        );
+        assert!(changes[0]
+            .to_string()
+            .starts_with("shrunk 10 → 4 iterations"));
+
+        assert!(matches!(
+            &diff::diff(&before, &before)[..],
+            [Change::Unchanged { .. }]
+        ));
+        assert!(matches!(
+            &diff::diff(&before, &[])[..],
+            [Change::Removed(_)]
+        ));
+        assert!(matches!(&diff::diff(&[], &before)[..], [Change::New(_)]));
+
+        // B no longer causes itself, and a loop through other quantifiers appeared
+        let mut reshaped = before[0].clone();
+        reshaped
+            .template
+            .inter
+            .remove(&("B".to_owned(), "B".to_owned()));
+        let other = summaries(&test_graph_path());
+        let changes = diff::diff(&before, &[other[0].clone(), reshaped]);
+        assert!(matches!(
+            &changes[..],
+            [Change::New(_), Change::Shape { .. }]
+        ));
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    pub fn test_fix() {
+        // B produces a bigger term each time, which the next iteration's A matches
+        let mut text = String::new();
+        for i in 0..5 {
+            text.push_str(&format!("A{i} -> B{i}\n"));
+            text.push_str(&format!("A{i} [pattern=\"{{f(x)}}\" bind.x=\"g{i}(c)\"]\n"));
+            if i > 0 {
+                text.push_str(&format!("B{} -> A{i} [term=\"f(g{i}(c))\"]\n", i - 1));
+            }
+        }
+        let graph = edgelist::parse(&text).unwrap();
+        let s = graph.sorted[0].clone();
+        let pairs = get_mlg_from(&s, &find_ith_progenitor(&s, 1).unwrap()).unwrap();
+        let suggestions = fix::suggest(&pairs);
+        assert_eq!(suggestions.len(), 1);
+        let fix = &suggestions[0];
+        assert!(fix.breaks_loop);
+        assert_eq!(fix.quantifier, "A");
+        assert_eq!(fix.pattern.as_deref(), Some("{f(x)}"));
+        assert_eq!(fix.producers.iter().collect::<Vec<_>>(), ["B"]);
+        let (p, c) = &fix.edges[0];
+        assert_eq!(fix.terms(), [format!("f(g{}(c))", c.get_id())]);
+        assert_eq!(
+            fix.to_string(),
+            format!(
+                "change the trigger of A {{f(x)}}, matched by terms from B of the previous iteration\n  \
+                 {c} was triggered by term f(g{}(c)) produced by {p}\n  with x := g{}(c)\n",
+                c.get_id(),
+                c.get_id()
+            )
+        );
+
+        // no labels, but still a suggestion
+        let graph = test_graph_abcd();
+        let pairs = get_mlg(&graph.sorted[0]).unwrap();
+        let suggestions = fix::suggest(&pairs);
+        let names: Vec<&str> = suggestions.iter().map(|s| s.quantifier.as_str()).collect();
+        assert_eq!(names, ["B", "C", "D"]);
+        assert!(suggestions
+            .iter()
+            .all(|s| !s.breaks_loop && s.terms().is_empty()));
+    }
+
+    #[test]
+    pub fn test_report() {
+        let graph = test_graph_abcd();
+        let ranked = severity::rank(&graph, &[graph.sorted[0].clone()]);
+        let html = report::write("abcd <test>", &graph, &ranked);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>abcd &lt;test&gt;</title>"));
+        assert!(html.contains("40 instantiations of 4 quantifiers"));
+        assert!(html.contains("A → B"));
+        assert_eq!(html.matches("<svg").count(), 1);
+        // one box per unrolled node and one row per pair
+        assert_eq!(html.matches("<rect").count(), 40);
+        assert_eq!(
+            html.matches("<tr>").count(),
+            1 + ranked[0].pairing.pairs.len()
+        );
+        // nothing to fetch
+        assert!(!html.contains("src=") && !html.contains("href=") && !html.contains("<script"));
+
+        let html = report::write("abc", &graph, &[]);
+        assert!(html.contains("No matching loop found."));
+
+        // attributes and edge labels in the tooltips, matched terms in the table
+        let mut text = String::new();
+        for i in 0..5 {
+            text.push_str(&format!("A{i} -> B{i}\n"));
+            text.push_str(&format!("A{i} [pattern=\"{{f(x)}}\" bind.x=\"g{i}(c)\"]\n"));
+            if i > 0 {
+                text.push_str(&format!("B{} -> A{i} [term=\"f(g{i}(c))\"]\n", i - 1));
+            }
+        }
+        let graph = edgelist::parse(&text).unwrap();
+        let ranked = severity::rank(&graph, &[graph.sorted[0].clone()]);
+        let html = report::write("ab", &graph, &ranked);
+        assert!(html.contains("<title>A4 (iteration 5)\n{pattern: {f(x)}, bindings: [x := g4(c)]}"));
+        assert!(html.contains("A4 was triggered by term f(g4(c)) produced by B3</title>"));
+        assert!(html.contains("<th>term</th>"));
+        // the table numbers iterations like the drawing
+        let row = html.lines().find(|l| l.contains(">A4</td><td")).unwrap();
+        assert!(row.ends_with("<td>5</td><td>f(g4(c))</td></tr>"), "{}", row);
+    }
+
+    #[test]
+    pub fn test_validate() {
+        for graph in [
+            test_graph_abcd(),
+            test_graph_yorg(),
+            test_graph_layered(),
+            test_graph_path(),
+        ] {
+            assert_eq!(graph.validate(), vec![]);
+        }
+
+        let mut graph = test_graph_path();
+        let mut other = Graph::new();
+        other.add(Node::new("A".to_owned()));
+        let stranger = other.add(Node::new("A".to_owned()));
+        graph.nodes["B"][0].add(&stranger);
+        graph.sorted.sort();
+
+        let violations = graph.validate();
+        assert_eq!(
+            violations,
+            vec![validate::Violation::DanglingEdge {
+                node: "B0".to_owned(),
+                other: "A1".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_try_add_edge() {
+        let graph = test_graph_path();
+        let a0 = graph.nodes["A"][0].clone();
+        let b2 = graph.nodes["B"][2].clone();
+
+        // B2 -> A0 already exists, so A0 -> B2 would be a cycle
+        let err = b2.try_add_edge(&a0).unwrap_err();
+        assert_eq!(err.path.first(), Some(&"B2".to_owned()));
+        assert_eq!(err.path.last(), Some(&"A0".to_owned()));
+        assert!(a0.try_add_edge(&a0).is_err());
+        assert_eq!(graph.validate(), vec![]);
+
+        let b0 = graph.nodes["B"][0].clone();
+        assert!(b0.try_add_edge(&a0).is_ok());
+        assert!(b0.get_parents().contains(&a0));
+    }
+
+    #[test]
+    pub fn test_remove_and_compact() {
+        let mut graph = test_graph_abcd();
+        let b9 = graph.nodes["B"][9].clone();
+        let depth = graph.sorted.last().unwrap().get_depth();
+
+        assert!(graph.remove(&b9));
+        assert!(!graph.remove(&b9));
+        assert_eq!(graph.sorted.len(), 39);
+        assert!(graph.sorted.last().unwrap().get_depth() < depth);
+        assert_eq!(graph.validate(), vec![]);
+
+        // drop everything that caused nothing
+        graph.retain(|n| !n.get_children().is_empty());
+        assert_eq!(graph.validate(), vec![]);
+        // B0 and C9, whose only child was B9
+        assert_eq!(graph.sorted.len(), 37);
+        assert_eq!(graph.nodes["C"].len(), 9);
+
+        let b1 = graph.nodes["B"][0].clone();
+        assert_eq!(b1.get_id(), 1);
+        graph.compact();
+        assert_eq!(b1.get_id(), 0);
+        assert_eq!(graph.validate(), vec![]);
+        let a = graph.add(Node::new("A".to_owned()));
+        assert_eq!(a.get_id(), graph.nodes["A"].len() as u32 - 1);
+    }
+
+    // whatever `get_mlg_from` finds is a pairing among the ancestors of `s` in the oracle's sense,
+    // and, if `complete`, it finds one whenever the oracle does
+    fn assert_agrees_with_oracle(s: &RNode, s_prime: &RNode, complete: bool) {
+        let expected = oracle::pairings(s, s_prime);
+        match get_mlg_from(s, s_prime) {
+            Some(pairs) => {
+                let found = pairing::Pairing::from_search(pairs);
+                let cone = slice::backward_cone(s, &slice::SliceOptions::default());
+                assert!(found.pairs.keys().all(|n| cone.contains(n)));
+
+                // the search may shift the pairing towards the causes and drop `s`,
+                // then only the seeds it ended up with can be checked
+                let survives = found.pairs.get(s).is_some_and(|p| p.ptr_eq(s_prime));
+                let expected = if survives {
+                    expected
+                } else {
+                    oracle::pairings_from(&found.seeds())
+                };
+                let expected: Vec<String> = expected.iter().map(|p| p.to_string()).collect();
+                assert!(
+                    expected.contains(&found.to_string()),
+                    "{} ~> {}: found\n{}expected one of\n{}",
+                    s,
+                    s_prime,
+                    found,
+                    expected.join("--\n")
+                );
+            }
+            None => assert!(
+                !complete || expected.is_empty(),
+                "{} ~> {}: found nothing, expected one of\n{}",
+                s,
+                s_prime,
+                expected
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join("--\n")
+            ),
+        }
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
     pub fn test_get_mlg() {
         let graph = test_graph_path();
-        if let Some(pairs) = get_mlg(&graph.sorted[0]) {
-            println!("Pairs");
-            for (n, n_prime) in pairs {
-                println!("({n},{n_prime}), {:?}", n.get_iteration());
+        let s = &graph.sorted[0];
+        let pairs = get_mlg(s).expect("the path graph is a matching loop");
+        assert!(pairs
+            .iter()
+            .all(|(n, n_prime)| n.get_name() == n_prime.get_name()));
+
+        // the attempt that succeeded
+        let s_prime = (1..)
+            .map_while(|i| find_ith_progenitor(s, i))
+            .find(|s_prime| get_mlg_from(s, s_prime).is_some())
+            .unwrap();
+        assert_agrees_with_oracle(s, &s_prime, true);
+
+        // A1 causes both A3 and A4, it must not become its own partner
+        let graph = edgelist::parse("A0 -> A1 -> A2 -> A3 -> A4\nA0 -> A2\nA1 -> A3\nA1 -> A4\nA5")
+            .unwrap();
+        for s in graph.sorted.iter() {
+            if let Some(pairs) = get_mlg(s) {
+                assert!(
+                    pairs.iter().all(|(n, n_prime)| !n.ptr_eq(n_prime)),
+                    "from {}",
+                    s
+                );
             }
-        } else {
-            println!("Got None!");
         }
-        assert!(false);
+
+        // an iteration of 150 nodes takes more node actions than a search may spend
+        let names: Vec<String> = (0..150).map(|k| format!("Q{k}x")).collect();
+        let mut text = format!("loop {}\n", names.join(" "));
+        for w in names.windows(2) {
+            text.push_str(&format!("{} -> {}\n", w[0], w[1]));
+        }
+        text.push_str("Q149x -> Q0x +1\niterations 3\n");
+        let graph = generator::GeneratorSpec::parse(&text)
+            .unwrap()
+            .generate()
+            .graph;
+        assert!(get_mlg(&graph.nodes["Q149x"][2]).is_none());
+        assert!(severity::rank(&graph, &severity::default_starts(&graph)).is_empty());
     }
 
     #[test]
-    pub fn test_sorted() {
-        let graph = test_graph_layered();
-        for n in graph.sorted.iter() {
-            println!("{}: {}", n, n.borrow().depth);
+    pub fn test_oracle() {
+        for graph in [
+            test_graph_abcd(),
+            test_graph_yorg(),
+            test_graph_layered(),
+            test_graph_path(),
+        ] {
+            for s in graph.sorted.iter().take(4) {
+                for i in 1..=3 {
+                    if let Some(s_prime) = find_ith_progenitor(s, i) {
+                        assert_agrees_with_oracle(s, &s_prime, true);
+                    }
+                }
+            }
+        }
+
+        for seed in 0..20 {
+            let planted = random::plant(&random::RandomSpec {
+                seed,
+                noise: 10,
+                ..Default::default()
+            });
+            let s = planted.loops[0]
+                .iterations
+                .last()
+                .unwrap()
+                .iter()
+                .min()
+                .unwrap();
+            if let Some(s_prime) = find_ith_progenitor(s, 1) {
+                assert_agrees_with_oracle(s, &s_prime, true);
+            }
         }
-        // assert!(false);
     }
 
     #[test]
-    pub fn test_priorty_queue() {
+    pub fn test_sorted() {
+        let graph = test_graph_layered();
+        assert!(graph
+            .sorted
+            .windows(2)
+            .all(|w| w[0].get_depth() <= w[1].get_depth()));
+        // the first causes of the last iteration come last
+        let deepest: Vec<String> = graph.sorted[graph.sorted.len() - 2..]
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        assert_eq!(deepest, ["Y19", "C29"]);
+    }
+
+    fn test_graph_abc() -> Graph {
         let mut graph = Graph::new();
 
         for i in 0..10 {
@@ -494,9 +1684,62 @@ pub mod tests {
             }
         }
         graph.sorted.sort();
+        graph
+    }
 
-        get_mlg(&graph.sorted[0]);
+    // `get_mlg` from each of the shallowest nodes, pairs ordered by name and id
+    fn snapshot(graph: &Graph) -> String {
+        let mut out = String::new();
+        for s in graph.sorted.iter().take(4) {
+            out += &format!("# from {}\n", s);
+            let Some(pairs) = get_mlg(s) else {
+                out += "none\n";
+                continue;
+            };
+            let mut lines: Vec<(String, u32, String)> = pairs
+                .iter()
+                .map(|(n, n_prime)| {
+                    let i = n.get_iteration().map_or("-".to_owned(), |i| i.to_string());
+                    let line = format!("{} {} {}\n", n, n_prime, i);
+                    (n.get_name(), n.get_id(), line)
+                })
+                .collect();
+            lines.sort();
+            out.extend(lines.into_iter().map(|(_, _, line)| line));
+        }
+        out
+    }
 
-        // assert!(false);
+    // compares with tests/golden/<name>.txt, or overwrites it if MLG_BLESS is set
+    fn assert_golden(name: &str, actual: &str) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{}.txt", name));
+        if std::env::var_os("MLG_BLESS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            expected == actual,
+            "{} is out of date, check the output below and run `MLG_BLESS=1 cargo test golden` to update it\n{}",
+            path.display(),
+            actual
+        );
+    }
+
+    #[test]
+    pub fn test_golden() {
+        let fixtures = [
+            ("abcd", test_graph_abcd()),
+            ("yorg", test_graph_yorg()),
+            ("layered", test_graph_layered()),
+            ("path", test_graph_path()),
+            ("abc", test_graph_abc()),
+        ];
+        for (name, graph) in fixtures.iter() {
+            assert_golden(name, &snapshot(graph));
+        }
     }
 }
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+use regex::Regex;
+
+/// One step of name normalization.
+#[derive(Debug, Clone)]
+pub enum Rule {
+    /// Replaces every match of the regex; the replacement may refer to capture groups (`$1`).
+    Rewrite(Regex, String),
+    /// Drops everything from the last occurrence of the separator on, if what follows it is a number
+    /// (e.g. with separator `@`, `inv@12` becomes `inv`).
+    StripNumericSuffix(String),
+}
+
+impl Rule {
+    fn apply(&self, name: &str) -> String {
+        match self {
+            Rule::Rewrite(re, replacement) => {
+                re.replace_all(name, replacement.as_str()).into_owned()
+            }
+            Rule::StripNumericSuffix(sep) => match name.rsplit_once(sep.as_str()) {
+                Some((stem, suffix))
+                    if !stem.is_empty()
+                        && !suffix.is_empty()
+                        && suffix.chars().all(|c| c.is_ascii_digit()) =>
+                {
+                    stem.to_owned()
+                }
+                _ => name.to_owned(),
+            },
+        }
+    }
+}
+
+/// Maps the quantifier names found in a trace to the names nodes are grouped by,
+/// so that instances of one axiom whose generated names differ are recognised as one quantifier.
+/// Rules are applied in order, then the result is looked up in the alias table.
+#[derive(Debug, Clone, Default)]
+pub struct Normalizer {
+    pub rules: Vec<Rule>,
+    pub aliases: HashMap<String, String>,
+}
+
+/// A malformed line in a normalization config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Normalizer {
+    pub fn new() -> Normalizer {
+        Normalizer {
+            rules: vec![],
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Strips the suffixes Boogie and Viper commonly append to generated quantifier names:
+    /// skolem-style `!n` ids (`k!12`) and `@n` unique ids (`inv@7`).
+    pub fn verifier_defaults() -> Normalizer {
+        let mut n = Normalizer::new();
+        n.strip_numeric_suffix("!");
+        n.strip_numeric_suffix("@");
+        n
+    }
+
+    pub fn rewrite(&mut self, pattern: &str, replacement: &str) -> Result<(), regex::Error> {
+        self.rules
+            .push(Rule::Rewrite(Regex::new(pattern)?, replacement.to_owned()));
+        Ok(())
+    }
+
+    pub fn strip_numeric_suffix(&mut self, sep: &str) {
+        self.rules.push(Rule::StripNumericSuffix(sep.to_owned()));
+    }
+
+    pub fn alias(&mut self, from: &str, to: &str) {
+        self.aliases.insert(from.to_owned(), to.to_owned());
+    }
+
+    pub fn normalize(&self, name: &str) -> String {
+        let name = self
+            .rules
+            .iter()
+            .fold(name.to_owned(), |name, rule| rule.apply(&name));
+        match self.aliases.get(&name) {
+            Some(alias) => alias.clone(),
+            None => name,
+        }
+    }
+
+    /// Parses a normalization config, one directive per line:
+    ///
+    /// ```text
+    /// # comments and blank lines are ignored
+    /// rewrite \.l[0-9]+$ =>
+    /// strip @
+    /// alias stdinbpl.123:15 = append_assoc
+    /// ```
+    pub fn parse(text: &str) -> Result<Normalizer, ConfigError> {
+        let mut n = Normalizer::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| ConfigError {
+                line: i + 1,
+                message,
+            };
+
+            let (directive, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            match directive {
+                "rewrite" => {
+                    let (pattern, replacement) = rest.split_once("=>").ok_or_else(|| {
+                        error("expected `rewrite <regex> => <replacement>`".to_owned())
+                    })?;
+                    n.rewrite(pattern.trim(), replacement.trim())
+                        .map_err(|e| error(e.to_string()))?;
+                }
+                "strip" if !rest.is_empty() => n.strip_numeric_suffix(rest),
+                "strip" => return Err(error("expected `strip <separator>`".to_owned())),
+                "alias" => {
+                    let (from, to) = rest
+                        .split_once('=')
+                        .ok_or_else(|| error("expected `alias <name> = <name>`".to_owned()))?;
+                    n.alias(from.trim(), to.trim());
+                }
+                _ => return Err(error(format!("unknown directive `{}`", directive))),
+            }
+        }
+        Ok(n)
+    }
+
+    /// Reads a normalization config from a file, see `parse`.
+    pub fn load(path: &Path) -> Result<Normalizer, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(Normalizer::parse(&text)?)
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::zip;
+
+use crate::graph::*;
+use crate::pairing::Pairing;
+
+// An exhaustive reference for `get_mlg_from`, only meant for small graphs in tests:
+// it tries both ways of placing every node the search reaches, so it takes time exponential
+// in the number of ancestors of the start node.
+//
+// A pairing seeded with pairs `(s, s')` (a start node and its progenitor) assigns iterations
+// 0, 1 and 2 to nodes and maps nodes of iteration k to nodes of iteration k + 1 such that
+// - every seed `s` is in iteration 0 and is mapped to its `s'`,
+// - paired nodes have the same quantifier and their parents have pairwise the same quantifiers,
+// - the parents of a node `n` of iteration 0 are mapped to the corresponding parents of its partner,
+// - every node of iteration 1 is the partner of a node of iteration 0
+//   (those that are also mapped further, to iteration 2, are where the loop starts),
+// - no node is in two iterations or is the partner of two nodes.
+//
+// Note that `get_mlg_from(s, s')` need not return a pairing seeded with `(s, s')`: when it runs
+// into a third iteration it shifts its pairs one iteration towards the causes, which may drop `s`.
+// What it returns should still be a pairing seeded with its own `seeds()`.
+
+#[derive(Clone)]
+struct State {
+    pairs: BTreeMap<RNode, RNode>,
+    iterations: BTreeMap<RNode, u32>,
+    partners: BTreeSet<RNode>,
+    queue: Vec<(RNode, RNode)>,
+}
+
+fn corresponds(n: &RNode, n_prime: &RNode) -> bool {
+    let (n, n_prime) = (n.borrow(), n_prime.borrow());
+    n.symbol == n_prime.symbol
+        && n.parents.len() == n_prime.parents.len()
+        && zip(n.parents.iter(), n_prime.parents.iter())
+            .all(|(p, q)| p.get_symbol() == q.get_symbol())
+}
+
+impl State {
+    // puts `n` in iteration `i` unless it already is in another one
+    fn place(&mut self, n: &RNode, i: u32) -> bool {
+        *self.iterations.entry(n.clone()).or_insert(i) == i
+    }
+
+    fn pair(&mut self, n: &RNode, n_prime: &RNode) -> bool {
+        if !self.partners.insert(n_prime.clone()) {
+            return false;
+        }
+        self.pairs.insert(n.clone(), n_prime.clone());
+        true
+    }
+
+    // places `n` in iteration 0 and queues its parents
+    fn first(mut self, n: &RNode, n_prime: &RNode) -> Option<State> {
+        if !(self.place(n, 0) && self.place(n_prime, 1) && self.pair(n, n_prime)) {
+            return None;
+        }
+        self.queue
+            .extend(zip(n.get_parents(), n_prime.get_parents()));
+        Some(self)
+    }
+
+    // places `n` in iteration 1, where the loop starts
+    fn start(mut self, n: &RNode, n_prime: &RNode) -> Option<State> {
+        if !(self.place(n, 1) && self.place(n_prime, 2) && self.pair(n, n_prime)) {
+            return None;
+        }
+        Some(self)
+    }
+
+    fn complete(&self) -> bool {
+        self.iterations
+            .iter()
+            .filter(|(_, i)| **i == 1)
+            .all(|(n, _)| self.partners.contains(n))
+    }
+}
+
+fn explore(mut state: State, found: &mut Vec<Pairing>) {
+    while let Some((n, n_prime)) = state.queue.pop() {
+        if !corresponds(&n, &n_prime) {
+            return;
+        }
+        if let Some(x) = state.pairs.get(&n) {
+            if x != &n_prime {
+                return;
+            }
+            continue;
+        }
+        match state.iterations.get(&n) {
+            Some(1) => match state.start(&n, &n_prime) {
+                Some(s) => state = s,
+                None => return,
+            },
+            Some(_) => return,
+            None => {
+                if let Some(s) = state.clone().start(&n, &n_prime) {
+                    explore(s, found);
+                }
+                match state.first(&n, &n_prime) {
+                    Some(s) => state = s,
+                    None => return,
+                }
+            }
+        }
+    }
+
+    if state.complete() {
+        found.push(Pairing {
+            pairs: state.pairs,
+            iterations: state.iterations,
+        });
+    }
+}
+
+/// All pairings of the ancestors of `s` with those of `s_prime`, see above.
+pub fn pairings(s: &RNode, s_prime: &RNode) -> Vec<Pairing> {
+    pairings_from(&[(s.clone(), s_prime.clone())])
+}
+
+/// All pairings seeded with `seeds`, see above.
+pub fn pairings_from(seeds: &[(RNode, RNode)]) -> Vec<Pairing> {
+    let mut found = vec![];
+    let mut state = Some(State {
+        pairs: BTreeMap::new(),
+        iterations: BTreeMap::new(),
+        partners: BTreeSet::new(),
+        queue: vec![],
+    });
+    for (s, s_prime) in seeds {
+        if !corresponds(s, s_prime) {
+            return found;
+        }
+        state = state.and_then(|state| state.first(s, s_prime));
+    }
+    if let Some(state) = state {
+        explore(state, &mut found);
+    }
+    found.sort();
+    found
+}
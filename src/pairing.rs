@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::graph::*;
+
+/// A consistent pairing: `pairs` in the shape `get_mlg` returns and the iteration of every node it mentions.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Pairing {
+    pub pairs: BTreeMap<RNode, RNode>,
+    pub iterations: BTreeMap<RNode, u32>,
+}
+
+impl Pairing {
+    /// The iterations `get_mlg` left on the nodes it paired, in the same shape.
+    #[allow(clippy::mutable_key_type)]
+    pub fn from_search(pairs: BTreeMap<RNode, RNode>) -> Pairing {
+        let iterations = pairs
+            .iter()
+            .flat_map(|(n, n_prime)| [n, n_prime])
+            .filter_map(|n| n.get_iteration().map(|i| (n.clone(), i)))
+            .collect();
+        Pairing { pairs, iterations }
+    }
+
+    /// The pairs of iteration 0 whose first node does not cause another node of iteration 0.
+    pub fn seeds(&self) -> Vec<(RNode, RNode)> {
+        let first = |n: &RNode| self.iterations.get(n) == Some(&0);
+        self.pairs
+            .iter()
+            .filter(|(n, _)| first(n) && !n.get_children().iter().any(first))
+            .map(|(n, n_prime)| (n.clone(), n_prime.clone()))
+            .collect()
+    }
+}
+
+impl Display for Pairing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (n, n_prime) in self.pairs.iter() {
+            let i = self
+                .iterations
+                .get(n)
+                .map_or("?".to_owned(), |i| i.to_string());
+            writeln!(f, "({},{}), {}", n, n_prime, i)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::generator::{GeneratorSpec, LoopSpec, Rng, NOISE_NAMES};
+use crate::get_mlg;
+use crate::graph::*;
+
+/// Parameters of a random instantiation graph with planted matching loops.
+#[derive(Debug, Clone)]
+pub struct RandomSpec {
+    pub seed: u64,
+    /// Number of loops planted, each with its own random template and quantifiers.
+    pub loops: usize,
+    /// Iterations of every planted loop.
+    pub iterations: usize,
+    /// Upper bound on the number of nodes per iteration.
+    pub max_template_size: usize,
+    /// Number of unrelated instantiations, half of them created before the loops, half after.
+    pub noise: usize,
+    /// Probability with which a loop node gets an extra parent from the noise,
+    /// which breaks the regularity the search relies on.
+    pub interference: f64,
+}
+
+impl Default for RandomSpec {
+    fn default() -> Self {
+        RandomSpec {
+            seed: 0,
+            loops: 1,
+            iterations: 6,
+            max_template_size: 4,
+            noise: 30,
+            interference: 0.0,
+        }
+    }
+}
+
+/// One loop planted in a random graph.
+#[derive(Debug)]
+pub struct PlantedLoop {
+    pub template: LoopSpec,
+    /// `iterations[i][j]` is node `j` of the template in iteration `i`, in causal order.
+    pub iterations: Vec<Vec<RNode>>,
+}
+
+impl PlantedLoop {
+    /// The ground truth in the orientation `get_mlg` reports it:
+    /// every node mapped to its counterpart in the preceding iteration.
+    #[allow(clippy::mutable_key_type)]
+    pub fn pairing(&self) -> BTreeMap<RNode, RNode> {
+        let mut pairs = BTreeMap::new();
+        for w in self.iterations.windows(2) {
+            for (earlier, later) in w[0].iter().zip(w[1].iter()) {
+                pairs.insert(later.clone(), earlier.clone());
+            }
+        }
+        pairs
+    }
+
+    /// Iteration and template index of `n`, if it belongs to this loop.
+    pub fn position(&self, n: &RNode) -> Option<(usize, usize)> {
+        self.iterations
+            .iter()
+            .enumerate()
+            .find_map(|(i, it)| it.iter().position(|m| m.ptr_eq(n)).map(|j| (i, j)))
+    }
+}
+
+/// A random graph together with the loops planted in it.
+#[derive(Debug)]
+pub struct Planted {
+    pub graph: Graph,
+    pub loops: Vec<PlantedLoop>,
+    pub noise: Vec<RNode>,
+}
+
+const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/// A random, connected loop template over the quantifiers `L<k>_a`, `L<k>_b`, ...
+/// Every node but the first has a parent within the iteration
+/// and at least one edge leads into the next iteration.
+pub fn random_template(rng: &mut Rng, k: usize, max_size: usize) -> LoopSpec {
+    let size = 2 + rng.below(max_size.max(2) - 1);
+    let mut t = LoopSpec::default();
+
+    for j in 0..size {
+        // occasionally repeat a quantifier within an iteration
+        let letter = if j > 0 && rng.chance(0.2) {
+            rng.below(j)
+        } else {
+            j
+        };
+        t.nodes.push(format!(
+            "L{}_{}",
+            k,
+            LETTERS[letter % LETTERS.len()] as char
+        ));
+        if j > 0 {
+            t.intra.push((rng.below(j), j));
+            if j > 1 && rng.chance(0.3) {
+                let p = rng.below(j);
+                if !t.intra.contains(&(p, j)) {
+                    t.intra.push((p, j));
+                }
+            }
+        }
+    }
+
+    t.inter.push((rng.below(size), rng.below(size), 1));
+    if rng.chance(0.3) {
+        let edge = (rng.below(size), rng.below(size), 1 + rng.below(2) as u32);
+        if !t.inter.contains(&edge) {
+            t.inter.push(edge);
+        }
+    }
+    t
+}
+
+fn add_noise(graph: &mut Graph, rng: &mut Rng, pool: &[RNode], n: usize) -> Vec<RNode> {
+    let mut noise = vec![];
+    for _ in 0..n {
+        let node = graph.add(Node::new(
+            NOISE_NAMES[rng.below(NOISE_NAMES.len())].to_owned(),
+        ));
+        let candidates: Vec<&RNode> = pool.iter().chain(noise.iter()).collect();
+        if !candidates.is_empty() {
+            for _ in 0..rng.below(3) {
+                let p = candidates[rng.below(candidates.len())];
+                if !node.get_parents().iter().any(|q| q.ptr_eq(p)) {
+                    node.add(p);
+                }
+            }
+        }
+        noise.push(node);
+    }
+    noise
+}
+
+/// Builds a random graph: unrelated noise, then the planted loops (possibly with extra
+/// parents from that noise), then more noise caused by random earlier nodes.
+pub fn plant(spec: &RandomSpec) -> Planted {
+    let mut rng = Rng::new(spec.seed);
+    let mut graph = Graph::new();
+
+    let mut noise = add_noise(&mut graph, &mut rng, &[], spec.noise / 2);
+
+    let mut loops = vec![];
+    for k in 0..spec.loops {
+        let template = random_template(&mut rng, k, spec.max_template_size);
+        let generated = GeneratorSpec::new(template.clone(), spec.iterations).generate_into(graph);
+        graph = generated.graph;
+        loops.push(PlantedLoop {
+            template,
+            iterations: generated.iterations,
+        });
+    }
+
+    if !noise.is_empty() {
+        for l in loops.iter() {
+            for n in l.iterations.iter().flatten() {
+                if rng.chance(spec.interference) {
+                    n.add(&noise[rng.below(noise.len())]);
+                }
+            }
+        }
+    }
+
+    let pool: Vec<RNode> = graph.sorted.clone();
+    noise.extend(add_noise(
+        &mut graph,
+        &mut rng,
+        &pool,
+        spec.noise - spec.noise / 2,
+    ));
+
+    graph.sorted.sort();
+    Planted {
+        graph,
+        loops,
+        noise,
+    }
+}
+
+/// How `get_mlg` fared on a batch of random graphs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Detection {
+    /// Planted loops searched for.
+    pub loops: usize,
+    /// Loops for which `get_mlg`, started in the last iteration, returned a pairing that agrees with the planted one.
+    pub detected: usize,
+    /// Loops for which `get_mlg` returned `None`.
+    pub missed: usize,
+    /// Loops for which `get_mlg` returned a pairing that contradicts the planted one.
+    pub wrong: usize,
+    /// Pairs returned across all searches that are not pairs of corresponding nodes of one planted loop.
+    pub false_pairs: usize,
+    /// Searches started from noise that reported a loop.
+    pub spurious: usize,
+}
+
+impl Detection {
+    pub fn rate(&self) -> f64 {
+        if self.loops == 0 {
+            return 0.0;
+        }
+        self.detected as f64 / self.loops as f64
+    }
+}
+
+// number of pairs that do not relate corresponding nodes of one loop at a common distance
+// (pairs of another loop or at another distance than the majority count as false)
+#[allow(clippy::mutable_key_type)]
+fn false_pairs(planted: &Planted, pairs: &BTreeMap<RNode, RNode>) -> usize {
+    let mut distances: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut unexplained = 0;
+    for (n, n_prime) in pairs.iter() {
+        let explained = planted.loops.iter().enumerate().find_map(|(k, l)| {
+            match (l.position(n), l.position(n_prime)) {
+                (Some((i, j)), Some((i_prime, j_prime))) if j == j_prime && i > i_prime => {
+                    Some((k, i - i_prime))
+                }
+                _ => None,
+            }
+        });
+        match explained {
+            Some(key) => *distances.entry(key).or_default() += 1,
+            None => unexplained += 1,
+        }
+    }
+    let most = distances.values().max().copied().unwrap_or(0);
+    unexplained + distances.values().sum::<usize>() - most
+}
+
+/// Runs `get_mlg` on one random graph per seed `spec.seed, spec.seed + 1, ...`:
+/// once per planted loop from the shallowest node of its last iteration,
+/// and once from a shallowest noise node of each noise quantifier.
+pub fn measure(spec: &RandomSpec, runs: usize) -> Detection {
+    let mut d = Detection::default();
+    for r in 0..runs {
+        let planted = plant(&RandomSpec {
+            seed: spec.seed + r as u64,
+            ..spec.clone()
+        });
+
+        for l in planted.loops.iter() {
+            d.loops += 1;
+            let s = l
+                .iterations
+                .last()
+                .and_then(|it| it.iter().min())
+                .expect("planted loops are not empty");
+            match get_mlg(s) {
+                None => d.missed += 1,
+                Some(pairs) => {
+                    let wrong = false_pairs(&planted, &pairs);
+                    d.false_pairs += wrong;
+                    if wrong == 0 && !pairs.is_empty() {
+                        d.detected += 1;
+                    } else {
+                        d.wrong += 1;
+                    }
+                }
+            }
+        }
+
+        for name in NOISE_NAMES {
+            let Some(s) = planted.noise.iter().filter(|n| n.get_name() == name).min() else {
+                continue;
+            };
+            match get_mlg(s) {
+                None => {}
+                Some(pairs) => {
+                    d.false_pairs += false_pairs(&planted, &pairs);
+                    d.spurious += 1;
+                }
+            }
+        }
+    }
+    d
+}
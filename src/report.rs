@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::graph::*;
+use crate::severity::Severity;
+
+// A single HTML page without scripts or external resources, so it can be attached to an issue
+// and opened anywhere. Each loop is drawn as an SVG with one column per iteration in causal order
+// and the nodes of a column in the order `unroll` gives them, colored by iteration. Hovering
+// over a node shows its attributes and the labels of the edges to its parents.
+
+// the drawing shows at most this many iterations, the first ones
+const MAX_ITERATIONS: usize = 12;
+const COLUMN: usize = 110;
+const ROW: usize = 40;
+const BOX_WIDTH: usize = 70;
+const BOX_HEIGHT: usize = 24;
+const MARGIN: usize = 20;
+
+const COLORS: [&str; 8] = [
+    "#8dd3c7", "#ffffb3", "#bebada", "#fb8072", "#80b1d3", "#fdb462", "#b3de69", "#fccde5",
+];
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+pre { background: #f6f6f6; padding: 1em; overflow-x: auto; }
+table { border-collapse: collapse; margin: 1em 0; }
+td, th { border: 1px solid #ccc; padding: 0.2em 0.8em; font-family: monospace; }
+svg { display: block; margin: 1em 0; }
+svg text { font-family: monospace; font-size: 12px; }
+";
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn color(iteration: usize) -> &'static str {
+    COLORS[iteration % COLORS.len()]
+}
+
+// the iteration of every unrolled node, counted from the first in causal order
+#[allow(clippy::mutable_key_type)]
+fn iterations(loop_: &Severity) -> HashMap<RNode, usize> {
+    let mut iterations = HashMap::new();
+    for (i, it) in loop_.unrolled.iterations.iter().enumerate() {
+        for n in it {
+            iterations.insert(n.clone(), i);
+        }
+    }
+    iterations
+}
+
+// the tooltip of a node: its iteration, what the trace says about it and why it depends on its parents
+fn tooltip(n: &RNode, iteration: usize) -> String {
+    let mut lines = vec![format!("{} (iteration {})", n, iteration)];
+    if let Some(attributes) = n.get_attributes() {
+        lines.push(attributes.to_string());
+    }
+    for p in n.get_parents() {
+        if let Some(label) = n.get_edge_label(&p) {
+            lines.push(label.explain(&p, n));
+        }
+    }
+    lines.join("\n")
+}
+
+// the terms `n` was matched on, from the labels of the edges to its parents
+fn terms(n: &RNode) -> String {
+    let terms: Vec<String> = n
+        .get_parents()
+        .iter()
+        .filter_map(|p| n.get_edge_label(p).and_then(|l| l.term))
+        .collect();
+    terms.join(", ")
+}
+
+#[allow(clippy::mutable_key_type)]
+fn svg(s: &mut String, id: usize, loop_: &Severity) {
+    let shown = &loop_.unrolled.iterations[..loop_.unrolled.count().min(MAX_ITERATIONS)];
+    let rows = shown.first().map_or(0, |it| it.len());
+    let mut position: HashMap<RNode, (usize, usize)> = HashMap::new();
+    for (i, it) in shown.iter().enumerate() {
+        for (j, n) in it.iter().enumerate() {
+            let x = MARGIN + i * COLUMN;
+            let y = MARGIN + j * ROW;
+            position.insert(n.clone(), (x, y));
+        }
+    }
+
+    writeln!(
+        s,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+        2 * MARGIN + shown.len().saturating_sub(1) * COLUMN + BOX_WIDTH,
+        2 * MARGIN + rows.saturating_sub(1) * ROW + BOX_HEIGHT
+    )
+    .unwrap();
+    writeln!(
+        s,
+        "<defs><marker id=\"arrow{id}\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" \
+         markerWidth=\"6\" markerHeight=\"6\" orient=\"auto\"><path d=\"M0,0 L10,5 L0,10 z\" fill=\"#555\"/></marker></defs>"
+    )
+    .unwrap();
+
+    // edges below the nodes
+    for it in shown {
+        for n in it {
+            let (cx, cy) = position[n];
+            for p in n.get_parents() {
+                let Some(&(px, py)) = position.get(&p) else {
+                    continue;
+                };
+                let (py, cy) = (py + BOX_HEIGHT / 2, cy + BOX_HEIGHT / 2);
+                if px == cx {
+                    // within an iteration, bend out to the right of the column
+                    let (x, bend) = (px + BOX_WIDTH, px + BOX_WIDTH + COLUMN / 4);
+                    writeln!(
+                        s,
+                        "<path d=\"M{x},{py} Q{bend},{} {x},{cy}\" fill=\"none\" stroke=\"#555\" marker-end=\"url(#arrow{id})\"/>",
+                        (py + cy) / 2
+                    )
+                    .unwrap();
+                } else {
+                    let (x1, x2) = if px < cx {
+                        (px + BOX_WIDTH, cx)
+                    } else {
+                        (px, cx + BOX_WIDTH)
+                    };
+                    writeln!(
+                        s,
+                        "<line x1=\"{x1}\" y1=\"{py}\" x2=\"{x2}\" y2=\"{cy}\" stroke=\"#555\" marker-end=\"url(#arrow{id})\"/>"
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+    for (i, it) in shown.iter().enumerate() {
+        for n in it {
+            let (x, y) = position[n];
+            writeln!(
+                s,
+                "<g><title>{}</title><rect x=\"{x}\" y=\"{y}\" width=\"{BOX_WIDTH}\" height=\"{BOX_HEIGHT}\" rx=\"4\" \
+                 fill=\"{}\" stroke=\"#333\"/><text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text></g>",
+                escape(&tooltip(n, i + 1)),
+                color(i),
+                x + BOX_WIDTH / 2,
+                y + BOX_HEIGHT / 2 + 4,
+                escape(&n.to_string())
+            )
+            .unwrap();
+        }
+    }
+    writeln!(s, "</svg>").unwrap();
+}
+
+#[allow(clippy::mutable_key_type)]
+fn cell(s: &mut String, n: &RNode, iterations: &HashMap<RNode, usize>) {
+    match iterations.get(n) {
+        Some(i) => write!(s, "<td style=\"background: {}\">", color(*i)).unwrap(),
+        None => write!(s, "<td>").unwrap(),
+    }
+    write!(s, "{}</td>", escape(&n.to_string())).unwrap();
+}
+
+#[allow(clippy::mutable_key_type)]
+fn pairing_table(s: &mut String, loop_: &Severity) {
+    let iterations = iterations(loop_);
+    writeln!(s, "<table>").unwrap();
+    writeln!(
+        s,
+        "<tr><th>n</th><th>n′</th><th>iteration</th><th>term</th></tr>"
+    )
+    .unwrap();
+    for (n, n_prime) in loop_.pairing.pairs.iter() {
+        write!(s, "<tr>").unwrap();
+        cell(s, n, &iterations);
+        cell(s, n_prime, &iterations);
+        // numbered like the drawing, not like the pairing
+        let i = iterations
+            .get(n)
+            .map_or("?".to_owned(), |i| (i + 1).to_string());
+        writeln!(s, "<td>{}</td><td>{}</td></tr>", i, escape(&terms(n))).unwrap();
+    }
+    writeln!(s, "</table>").unwrap();
+}
+
+/// Writes the statistics of `graph` and the loops found in it, as ranked by `severity::rank`,
+/// as a self-contained HTML page.
+pub fn write(title: &str, graph: &Graph, loops: &[Severity]) -> String {
+    let mut s = String::new();
+    writeln!(s, "<!DOCTYPE html>").unwrap();
+    writeln!(s, "<html><head><meta charset=\"utf-8\">").unwrap();
+    writeln!(s, "<title>{}</title>", escape(title)).unwrap();
+    writeln!(s, "<style>{}</style></head><body>", STYLE).unwrap();
+    writeln!(s, "<h1>{}</h1>", escape(title)).unwrap();
+
+    writeln!(s, "<h2>Graph</h2>").unwrap();
+    writeln!(s, "<pre>{}</pre>", escape(&graph.stats().to_string())).unwrap();
+
+    writeln!(s, "<h2>Matching loops</h2>").unwrap();
+    if loops.is_empty() {
+        writeln!(s, "<p>No matching loop found.</p>").unwrap();
+    }
+    for (k, loop_) in loops.iter().enumerate() {
+        writeln!(s, "<h3>#{}</h3>", k + 1).unwrap();
+        writeln!(
+            s,
+            "<p>{} instantiations ({:.1}%), {} iterations, max depth {}</p>",
+            loop_.attributable,
+            loop_.share * 100.0,
+            loop_.unrolled.count(),
+            loop_.max_depth
+        )
+        .unwrap();
+        writeln!(s, "<pre>{}</pre>", escape(&loop_.template.to_string())).unwrap();
+        if loop_.unrolled.count() > MAX_ITERATIONS {
+            writeln!(
+                s,
+                "<p>The first {} of {} iterations:</p>",
+                MAX_ITERATIONS,
+                loop_.unrolled.count()
+            )
+            .unwrap();
+        }
+        svg(&mut s, k, loop_);
+        pairing_table(&mut s, loop_);
+    }
+    writeln!(s, "</body></html>").unwrap();
+    s
+}
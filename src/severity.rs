@@ -0,0 +1,99 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Display;
+
+use crate::get_mlg;
+use crate::graph::*;
+use crate::pairing::Pairing;
+use crate::slice;
+use crate::template::LoopTemplate;
+use crate::unroll::{self, Unrolled};
+
+/// How much of a graph a matching loop is responsible for.
+#[derive(Debug, Clone)]
+pub struct Severity {
+    pub template: LoopTemplate,
+    pub unrolled: Unrolled,
+    /// Instantiations in the unrolled loop and everything they caused.
+    pub attributable: usize,
+    /// `attributable` as a fraction of all instantiations in the graph.
+    pub share: f64,
+    /// Greatest depth of an instantiation in the unrolled loop.
+    pub max_depth: u32,
+    /// The pairs `get_mlg` found, with the iterations it assigned, which later searches overwrite on the nodes.
+    pub pairing: Pairing,
+}
+
+impl Severity {
+    /// The cost of a result of `get_mlg` on `graph`; the iterations it assigned must still be set on the nodes.
+    #[allow(clippy::mutable_key_type)]
+    pub fn of(graph: &Graph, pairs: &BTreeMap<RNode, RNode>) -> Severity {
+        let unrolled = unroll::unroll(pairs);
+        let nodes: Vec<RNode> = unrolled.iterations.iter().flatten().cloned().collect();
+        let attributable = slice::forward_cone_of(&nodes).len();
+        Severity {
+            template: LoopTemplate::from_pairs(pairs),
+            attributable,
+            share: attributable as f64 / graph.sorted.len().max(1) as f64,
+            max_depth: nodes.iter().map(|n| n.get_depth()).max().unwrap_or(0),
+            unrolled,
+            pairing: Pairing::from_search(pairs.clone()),
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} instantiations ({:.1}%), {} iterations, max depth {}",
+            self.attributable,
+            self.share * 100.0,
+            self.unrolled.count(),
+            self.max_depth
+        )?;
+        write!(f, "{}", self.template)
+    }
+}
+
+/// Searches for a matching loop from each of `starts` and ranks the loops found,
+/// most attributable instantiations first. A loop found from several starts, possibly with its
+/// iterations cut at different places, is reported once, by its most costly result.
+#[allow(clippy::mutable_key_type)]
+pub fn rank(graph: &Graph, starts: &[RNode]) -> Vec<Severity> {
+    let mut found: Vec<(Severity, HashSet<RNode>)> = vec![];
+    for s in starts {
+        let Some(pairs) = get_mlg(s) else {
+            continue;
+        };
+        let severity = Severity::of(graph, &pairs);
+        let nodes: HashSet<RNode> = severity
+            .unrolled
+            .iterations
+            .iter()
+            .flatten()
+            .cloned()
+            .collect();
+        match found
+            .iter_mut()
+            .find(|(_, other)| !other.is_disjoint(&nodes))
+        {
+            Some(same) if same.0.attributable < severity.attributable => *same = (severity, nodes),
+            Some(_) => {}
+            None => found.push((severity, nodes)),
+        }
+    }
+    let mut ranked: Vec<Severity> = found.into_iter().map(|(s, _)| s).collect();
+    ranked.sort_by_key(|s| std::cmp::Reverse(s.attributable));
+    ranked
+}
+
+/// The shallowest instantiation of every quantifier, in order of depth.
+pub fn default_starts(graph: &Graph) -> Vec<RNode> {
+    let mut starts: Vec<RNode> = graph
+        .nodes
+        .values()
+        .filter_map(|ns| ns.iter().min().cloned())
+        .collect();
+    starts.sort();
+    starts
+}
@@ -0,0 +1,210 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::graph::*;
+
+/// Bounds a forward traversal.
+/// `max_distance` limits how many edges away from the seed a descendant may be,
+/// `quantifier` restricts the reported nodes to instantiations of one quantifier
+/// (the traversal itself still passes through every node).
+#[derive(Debug, Clone, Default)]
+pub struct SliceOptions {
+    pub max_distance: Option<u32>,
+    pub quantifier: Option<String>,
+}
+
+impl SliceOptions {
+    fn admits(&self, n: &RNode) -> bool {
+        match &self.quantifier {
+            Some(q) => *n.borrow().name == **q,
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Forward,
+    Backward,
+}
+
+// breadth first so that max_distance is the length of the shortest path
+#[allow(clippy::mutable_key_type)]
+fn search(seeds: &[RNode], opts: &SliceOptions, dir: Direction) -> Vec<RNode> {
+    let mut visited: HashSet<RNode> = seeds.iter().cloned().collect();
+    let mut frontier: VecDeque<(RNode, u32)> = seeds.iter().map(|s| (s.clone(), 0)).collect();
+    let mut found = vec![];
+
+    while let Some((n, d)) = frontier.pop_front() {
+        if opts.max_distance.is_some_and(|max| d >= max) {
+            continue;
+        }
+
+        let next = match dir {
+            Direction::Forward => n.get_children(),
+            Direction::Backward => n.get_parents(),
+        };
+        for m in next {
+            if visited.insert(m.clone()) {
+                if opts.admits(&m) {
+                    found.push(m.clone());
+                }
+                frontier.push_back((m, d + 1));
+            }
+        }
+    }
+
+    found.sort();
+    found
+}
+
+fn cone(n: &RNode, opts: &SliceOptions, dir: Direction) -> Vec<RNode> {
+    let mut cone = search(std::slice::from_ref(n), opts, dir);
+    if opts.admits(n) {
+        cone.push(n.clone());
+        cone.sort();
+    }
+    cone
+}
+
+/// Returns every node caused (transitively) by `n`, excluding `n` itself, ordered by depth.
+pub fn descendants(n: &RNode, opts: &SliceOptions) -> Vec<RNode> {
+    search(std::slice::from_ref(n), opts, Direction::Forward)
+}
+
+/// Returns every node that (transitively) caused `n`, excluding `n` itself, ordered by depth.
+pub fn ancestors(n: &RNode, opts: &SliceOptions) -> Vec<RNode> {
+    search(std::slice::from_ref(n), opts, Direction::Backward)
+}
+
+/// Returns the downstream cone of `n`: `n` together with all of its descendants.
+pub fn forward_cone(n: &RNode, opts: &SliceOptions) -> Vec<RNode> {
+    cone(n, opts, Direction::Forward)
+}
+
+/// Returns the upstream cone of `n`: `n` together with all of its ancestors.
+pub fn backward_cone(n: &RNode, opts: &SliceOptions) -> Vec<RNode> {
+    cone(n, opts, Direction::Backward)
+}
+
+/// Returns all instantiations downstream of a matching loop graph,
+/// i.e. the paired nodes themselves and everything they caused.
+#[allow(clippy::mutable_key_type)]
+pub fn mlg_cone(pairs: &BTreeMap<RNode, RNode>) -> Vec<RNode> {
+    let seeds: Vec<RNode> = pairs.keys().chain(pairs.values()).cloned().collect();
+    forward_cone_of(&seeds)
+}
+
+/// Returns `nodes` together with everything they caused, ordered by depth.
+pub fn forward_cone_of(nodes: &[RNode]) -> Vec<RNode> {
+    let mut seeds = nodes.to_vec();
+    seeds.sort();
+    seeds.dedup();
+
+    let mut cone = search(&seeds, &SliceOptions::default(), Direction::Forward);
+    cone.extend(seeds);
+    cone.sort();
+    cone
+}
+
+/// An induced subgraph cut out of a larger graph.
+/// The copy is an independent `Graph` (with its own, densely renumbered ids);
+/// the correspondence to the original nodes is kept so that results computed
+/// on the slice can be mapped back.
+#[derive(Debug)]
+pub struct Subgraph {
+    pub graph: Graph,
+    to_original: HashMap<RNode, RNode>,
+    from_original: HashMap<RNode, RNode>,
+}
+
+impl Subgraph {
+    /// Returns the node of the original graph that `n` was copied from.
+    pub fn original(&self, n: &RNode) -> Option<RNode> {
+        self.to_original.get(n).cloned()
+    }
+
+    /// Returns the copy of the original node `n`, if it is part of the subgraph.
+    pub fn copy_of(&self, n: &RNode) -> Option<RNode> {
+        self.from_original.get(n).cloned()
+    }
+
+    pub fn contains_original(&self, n: &RNode) -> bool {
+        self.from_original.contains_key(n)
+    }
+}
+
+impl Graph {
+    /// Copies `nodes` and the edges between them into a new graph.
+    /// Ids are renumbered per name, preserving the relative order of the original ids.
+    #[allow(clippy::mutable_key_type)]
+    pub fn induced(&self, nodes: &[RNode]) -> Subgraph {
+        let mut members: Vec<RNode> = nodes.iter().filter(|n| self.contains(n)).cloned().collect();
+        members.sort_by(|x, y| {
+            let (x, y) = (x.borrow(), y.borrow());
+            x.name.cmp(&y.name).then(x.id.cmp(&y.id))
+        });
+        members.dedup();
+
+        let mut graph = Graph::new();
+        let mut to_original = HashMap::new();
+        let mut from_original = HashMap::new();
+
+        for m in members.iter() {
+            let mut node = Node::new(m.get_name());
+            node.attributes = m.borrow().attributes.clone();
+            let copy = graph.add(node);
+            to_original.insert(copy.clone(), m.clone());
+            from_original.insert(m.clone(), copy);
+        }
+
+        for m in members.iter() {
+            let copy = &from_original[m];
+            for p in m.borrow().parents.iter() {
+                if let Some(p_copy) = from_original.get(p) {
+                    copy.add(p_copy);
+                    if let Some(label) = m.get_edge_label(p) {
+                        copy.set_edge_label(p_copy, label);
+                    }
+                }
+            }
+        }
+
+        graph.sorted.sort();
+        Subgraph {
+            graph,
+            to_original,
+            from_original,
+        }
+    }
+
+    /// The subgraph induced by `n` and its ancestors.
+    pub fn ancestor_subgraph(&self, n: &RNode, opts: &SliceOptions) -> Subgraph {
+        self.induced(&backward_cone(n, opts))
+    }
+
+    /// The subgraph induced by `n` and its descendants.
+    pub fn descendant_subgraph(&self, n: &RNode, opts: &SliceOptions) -> Subgraph {
+        self.induced(&forward_cone(n, opts))
+    }
+
+    /// The subgraph induced by the nodes of a matching loop graph
+    /// whose iteration is one of `iterations` (all paired nodes if `iterations` is empty).
+    #[allow(clippy::mutable_key_type)]
+    pub fn mlg_subgraph(&self, pairs: &BTreeMap<RNode, RNode>, iterations: &[u32]) -> Subgraph {
+        let nodes: Vec<RNode> = pairs
+            .keys()
+            .chain(pairs.values())
+            .filter(|n| {
+                iterations.is_empty() || n.get_iteration().is_some_and(|i| iterations.contains(&i))
+            })
+            .cloned()
+            .collect();
+        self.induced(&nodes)
+    }
+
+    /// Extracts the downstream cone of `n` as a new graph.
+    /// Edges between cone members are kept, everything else is dropped.
+    pub fn forward_slice(&self, n: &RNode, opts: &SliceOptions) -> Graph {
+        self.descendant_subgraph(n, opts).graph
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+
+use crate::graph::*;
+use crate::symbol::Symbol;
+
+/// An overview of a graph, see `Graph::stats`.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    pub nodes: usize,
+    pub edges: usize,
+    /// Instantiations per quantifier, most frequent first.
+    pub quantifiers: Vec<(String, usize)>,
+    /// Number of nodes per depth.
+    pub depths: BTreeMap<u32, usize>,
+    /// Number of nodes per number of parents.
+    pub fan_in: BTreeMap<usize, usize>,
+    /// Number of nodes per number of children.
+    pub fan_out: BTreeMap<usize, usize>,
+    /// A longest chain of causes, from its first cause to its last consequence.
+    pub longest_chain: Vec<RNode>,
+    /// Per quantifier, how many of its instantiations have an instantiation of the same quantifier
+    /// among their ancestors, most first. Quantifiers that cause themselves are candidate loop roots.
+    pub self_ancestry: Vec<(String, usize)>,
+}
+
+impl Graph {
+    /// Counts what there is in the graph. Expects `sorted` to be sorted.
+    pub fn stats(&self) -> Stats {
+        let mut quantifiers: Vec<(String, usize)> = self
+            .nodes
+            .iter()
+            .map(|(name, ns)| (name.clone(), ns.len()))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        quantifiers.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+
+        let mut depths = BTreeMap::new();
+        let mut fan_in = BTreeMap::new();
+        let mut fan_out = BTreeMap::new();
+        let mut edges = 0;
+        for n in self.sorted.iter() {
+            let node = n.borrow();
+            *depths.entry(node.depth).or_default() += 1;
+            *fan_in.entry(node.parents.len()).or_default() += 1;
+            *fan_out.entry(node.children.len()).or_default() += 1;
+            edges += node.parents.len();
+        }
+
+        // from a deepest node down along children one less deep
+        let mut longest_chain = vec![];
+        let mut next = self.sorted.last().cloned();
+        while let Some(n) = next {
+            next = n
+                .get_children()
+                .into_iter()
+                .find(|c| c.get_depth() + 1 == n.get_depth());
+            longest_chain.push(n);
+        }
+
+        let mut self_ancestry: Vec<(String, usize)> = self.self_ancestry().into_iter().collect();
+        self_ancestry.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+
+        Stats {
+            nodes: self.sorted.len(),
+            edges,
+            quantifiers,
+            depths,
+            fan_in,
+            fan_out,
+            longest_chain,
+            self_ancestry,
+        }
+    }
+
+    // per quantifier, the number of its instantiations with an ancestor instantiating it
+    fn self_ancestry(&self) -> HashMap<String, usize> {
+        // the quantifiers of every node and its ancestors
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        self.fold_causes(|n, parents: &[&HashSet<Symbol>]| {
+            let node = n.borrow();
+            let mut own: HashSet<Symbol> = HashSet::new();
+            for ancestors in parents {
+                own.extend(ancestors.iter().copied());
+            }
+            if own.contains(&node.symbol) {
+                *counts.entry(node.name.to_string()).or_default() += 1;
+            }
+            own.insert(node.symbol);
+            own
+        });
+        counts
+    }
+}
+
+// at most this many lines per histogram, wider ones are grouped into ranges
+const MAX_ROWS: usize = 16;
+
+fn histogram<K: Copy + Into<u64>>(
+    f: &mut std::fmt::Formatter<'_>,
+    title: &str,
+    counts: &BTreeMap<K, usize>,
+) -> std::fmt::Result {
+    writeln!(f, "{}:", title)?;
+    let (Some(lo), Some(hi)) = (counts.keys().next(), counts.keys().last()) else {
+        return Ok(());
+    };
+    let (lo, hi): (u64, u64) = ((*lo).into(), (*hi).into());
+    let width = (hi - lo) / MAX_ROWS as u64 + 1;
+    let mut rows: BTreeMap<u64, usize> = BTreeMap::new();
+    for (k, count) in counts.iter() {
+        *rows.entry(((*k).into() - lo) / width).or_default() += count;
+    }
+    for (row, count) in rows {
+        let from = lo + row * width;
+        if width == 1 {
+            writeln!(f, "  {:>12}  {}", from, count)?;
+        } else {
+            writeln!(
+                f,
+                "  {:>12}  {}",
+                format!("{}-{}", from, from + width - 1),
+                count
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn chain(nodes: &[RNode]) -> String {
+    let names: Vec<String> = nodes.iter().map(|n| n.to_string()).collect();
+    if names.len() <= 12 {
+        names.join(" -> ")
+    } else {
+        format!(
+            "{} -> ... -> {}",
+            names[..5].join(" -> "),
+            names[names.len() - 5..].join(" -> ")
+        )
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} instantiations of {} quantifiers, {} edges",
+            self.nodes,
+            self.quantifiers.len(),
+            self.edges
+        )?;
+        writeln!(f, "instantiations per quantifier:")?;
+        for (q, count) in self.quantifiers.iter() {
+            writeln!(f, "  {:>12}  {}", q, count)?;
+        }
+        histogram(f, "nodes per depth", &self.depths)?;
+        let fan_in: BTreeMap<u32, usize> =
+            self.fan_in.iter().map(|(k, v)| (*k as u32, *v)).collect();
+        histogram(f, "nodes per number of parents", &fan_in)?;
+        let fan_out: BTreeMap<u32, usize> =
+            self.fan_out.iter().map(|(k, v)| (*k as u32, *v)).collect();
+        histogram(f, "nodes per number of children", &fan_out)?;
+        writeln!(
+            f,
+            "longest chain ({} instantiations): {}",
+            self.longest_chain.len(),
+            chain(&self.longest_chain)
+        )?;
+        writeln!(f, "instantiations caused by the same quantifier:")?;
+        for (q, count) in self.self_ancestry.iter() {
+            writeln!(f, "  {:>12}  {}", q, count)?;
+        }
+        Ok(())
+    }
+}
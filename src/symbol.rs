@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::rc::Rc;
+
+/// A compact handle for an interned quantifier name.
+/// Symbols are only meaningful relative to the `SymbolTable` (and so the `Graph`) that issued them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Placeholder carried by nodes that have not been added to a graph yet.
+    pub const UNINTERNED: Symbol = Symbol(u32::MAX);
+
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Interns quantifier names so that every node of a quantifier shares one allocation
+/// and names can be compared by symbol instead of by string.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    names: Vec<Rc<str>>,
+    symbols: HashMap<Rc<str>, Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable {
+            names: vec![],
+            symbols: HashMap::new(),
+        }
+    }
+
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(sym) = self.symbols.get(name) {
+            return *sym;
+        }
+        let sym = Symbol(self.names.len() as u32);
+        let name: Rc<str> = Rc::from(name);
+        self.names.push(name.clone());
+        self.symbols.insert(name, sym);
+        sym
+    }
+
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).copied()
+    }
+
+    pub fn resolve(&self, sym: Symbol) -> &Rc<str> {
+        &self.names[sym.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (Symbol(i as u32), n.as_ref()))
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::graph::*;
+
+/// The abstract shape of a matching loop, independent of the concrete instantiations it was found
+/// in, so that loops can be compared between traces: which quantifiers one iteration instantiates,
+/// how they cause each other within the iteration and how they cause the next iteration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LoopTemplate {
+    /// How often each quantifier is instantiated per iteration.
+    pub quantifiers: BTreeMap<String, usize>,
+    /// How many edges `(parent, child)` there are within an iteration.
+    pub intra: BTreeMap<(String, String), usize>,
+    /// How many edges `(parent, child)` lead from an iteration into the next one.
+    pub inter: BTreeMap<(String, String), usize>,
+}
+
+impl LoopTemplate {
+    /// The template of a result of `get_mlg`, read off its iteration 0 and the edges into it.
+    /// The iterations `get_mlg` assigned must still be set on the nodes.
+    #[allow(clippy::mutable_key_type)]
+    pub fn from_pairs(pairs: &BTreeMap<RNode, RNode>) -> LoopTemplate {
+        let mut t = LoopTemplate::default();
+        for n in pairs.keys().filter(|n| n.get_iteration() == Some(0)) {
+            *t.quantifiers.entry(n.get_name()).or_default() += 1;
+            for p in n.get_parents() {
+                let edge = (p.get_name(), n.get_name());
+                match p.get_iteration() {
+                    Some(0) => *t.intra.entry(edge).or_default() += 1,
+                    Some(1) => *t.inter.entry(edge).or_default() += 1,
+                    _ => {}
+                }
+            }
+        }
+        t
+    }
+
+    /// Number of instantiations per iteration.
+    pub fn len(&self) -> usize {
+        self.quantifiers.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quantifiers.is_empty()
+    }
+}
+
+fn counted(name: &str, count: usize) -> String {
+    if count == 1 {
+        name.to_owned()
+    } else {
+        format!("{}×{}", name, count)
+    }
+}
+
+// `A → B`, `B → (A, C×2)`
+fn write_edges(
+    f: &mut std::fmt::Formatter<'_>,
+    edges: &BTreeMap<(String, String), usize>,
+    suffix: &str,
+) -> std::fmt::Result {
+    let mut by_parent: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for ((p, c), count) in edges.iter() {
+        by_parent.entry(p).or_default().push(counted(c, *count));
+    }
+    for (p, children) in by_parent {
+        if children.len() == 1 {
+            writeln!(f, "{} → {}{}", p, children[0], suffix)?;
+        } else {
+            writeln!(f, "{} → ({}){}", p, children.join(", "), suffix)?;
+        }
+    }
+    Ok(())
+}
+
+impl Display for LoopTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let quantifiers: Vec<String> = self
+            .quantifiers
+            .iter()
+            .map(|(q, count)| counted(q, *count))
+            .collect();
+        writeln!(f, "per iteration: {}", quantifiers.join(", "))?;
+        write_edges(f, &self.intra, "")?;
+        write_edges(f, &self.inter, " next iteration")
+    }
+}
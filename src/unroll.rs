@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Display;
+
+use crate::graph::*;
+
+// Unrolling follows the loop from the two iterations `get_mlg` paired through the rest of the graph.
+// Iterations are lists of nodes in the order of iteration 0 of the pairing ("positions").
+// A relation `(child, index, parent, inter)` says that the `index`-th parent of the node at position
+// `child` is the node at position `parent` of the same iteration, or of the preceding one if `inter`.
+// A neighbouring iteration is found by looking up parents and children along the relations until
+// every position is filled; it is complete if all relations between its nodes hold.
+
+#[derive(Debug, Clone, Copy)]
+struct Relation {
+    child: usize,
+    index: usize,
+    parent: usize,
+    inter: bool,
+}
+
+struct Shape {
+    names: Vec<String>,
+    relations: Vec<Relation>,
+}
+
+/// How far a matching loop repeats in its graph.
+#[derive(Debug, Clone)]
+pub struct Unrolled {
+    /// Every complete iteration, from the first to the last in causal order,
+    /// each with the nodes in the same order.
+    pub iterations: Vec<Vec<RNode>>,
+}
+
+impl Unrolled {
+    /// How many complete iterations occurred.
+    pub fn count(&self) -> usize {
+        self.iterations.len()
+    }
+
+    /// The iteration the loop started with.
+    pub fn first(&self) -> &[RNode] {
+        self.iterations.first().map_or(&[], |it| it.as_slice())
+    }
+
+    /// The iteration the loop stopped after.
+    pub fn last(&self) -> &[RNode] {
+        self.iterations.last().map_or(&[], |it| it.as_slice())
+    }
+}
+
+fn list(nodes: &[RNode]) -> String {
+    nodes
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Display for Unrolled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} iterations", self.count())?;
+        writeln!(f, "first: {}", list(self.first()))?;
+        writeln!(f, "last: {}", list(self.last()))
+    }
+}
+
+fn holds(r: &Relation, child: &RNode, parent: &RNode) -> bool {
+    child
+        .borrow()
+        .parents
+        .get(r.index)
+        .is_some_and(|p| p.ptr_eq(parent))
+}
+
+impl Shape {
+    // iteration 0 of the pairing and the shape of the loop
+    #[allow(clippy::mutable_key_type)]
+    fn of(pairs: &BTreeMap<RNode, RNode>) -> (Shape, Vec<RNode>, Vec<RNode>) {
+        let mut first: Vec<RNode> = pairs
+            .keys()
+            .filter(|n| n.get_iteration() == Some(0))
+            .cloned()
+            .collect();
+        first.sort_by_key(|n| (n.get_name(), n.get_id()));
+        let second: Vec<RNode> = first.iter().map(|n| pairs[n].clone()).collect();
+
+        let position = |it: &[RNode], n: &RNode| it.iter().position(|m| m.ptr_eq(n));
+        let mut relations = vec![];
+        for (child, n) in first.iter().enumerate() {
+            for (index, p) in n.get_parents().iter().enumerate() {
+                if let Some(parent) = position(&first, p) {
+                    relations.push(Relation {
+                        child,
+                        index,
+                        parent,
+                        inter: false,
+                    });
+                } else if let Some(parent) = position(&second, p) {
+                    relations.push(Relation {
+                        child,
+                        index,
+                        parent,
+                        inter: true,
+                    });
+                }
+            }
+        }
+        let names = first.iter().map(|n| n.get_name()).collect();
+        (Shape { names, relations }, first, second)
+    }
+
+    // the iteration before `known` if `earlier`, else the one after it
+    #[allow(clippy::mutable_key_type)]
+    fn step(&self, known: &[RNode], earlier: bool, seen: &HashSet<RNode>) -> Option<Vec<RNode>> {
+        let mut new: Vec<Option<RNode>> = vec![None; self.names.len()];
+        let used = |n: &RNode, new: &[Option<RNode>]| {
+            seen.contains(n) || new.iter().flatten().any(|m| m.ptr_eq(n))
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for r in self.relations.iter() {
+                // where the two ends of `r` are in the new iteration, if they are
+                let (child, parent) = match (r.inter, earlier) {
+                    (false, _) => (new[r.child].clone(), new[r.parent].clone()),
+                    (true, true) => (Some(known[r.child].clone()), new[r.parent].clone()),
+                    (true, false) => (new[r.child].clone(), Some(known[r.parent].clone())),
+                };
+                let child_is_new = !r.inter || !earlier;
+                let parent_is_new = !r.inter || earlier;
+
+                if let (Some(c), None) = (&child, &parent) {
+                    if parent_is_new {
+                        let p = c.borrow().parents.get(r.index).cloned()?;
+                        if p.get_name() != self.names[r.parent] || used(&p, &new) {
+                            return None;
+                        }
+                        new[r.parent] = Some(p);
+                        changed = true;
+                    }
+                } else if let (None, Some(p)) = (&child, &parent) {
+                    if child_is_new {
+                        let c = p.get_children().into_iter().find(|c| {
+                            c.get_name() == self.names[r.child] && holds(r, c, p) && !used(c, &new)
+                        })?;
+                        new[r.child] = Some(c);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let new: Vec<RNode> = new.into_iter().collect::<Option<_>>()?;
+        let complete = self.relations.iter().all(|r| match (r.inter, earlier) {
+            (false, _) => holds(r, &new[r.child], &new[r.parent]),
+            (true, true) => holds(r, &known[r.child], &new[r.parent]),
+            (true, false) => holds(r, &new[r.child], &known[r.parent]),
+        });
+        complete.then_some(new)
+    }
+}
+
+/// Follows a result of `get_mlg` through its graph as far as the loop repeats.
+/// The iterations `get_mlg` assigned must still be set on the nodes.
+#[allow(clippy::mutable_key_type)]
+pub fn unroll(pairs: &BTreeMap<RNode, RNode>) -> Unrolled {
+    let (shape, first, second) = Shape::of(pairs);
+    if first.is_empty() {
+        return Unrolled { iterations: vec![] };
+    }
+    let mut seen: HashSet<RNode> = first.iter().chain(second.iter()).cloned().collect();
+
+    // iteration 1 of the pairing precedes iteration 0
+    let mut earlier = vec![second];
+    while let Some(it) = shape.step(earlier.last().unwrap(), true, &seen) {
+        seen.extend(it.iter().cloned());
+        earlier.push(it);
+    }
+    let mut later = vec![first];
+    while let Some(it) = shape.step(later.last().unwrap(), false, &seen) {
+        seen.extend(it.iter().cloned());
+        later.push(it);
+    }
+
+    earlier.reverse();
+    earlier.extend(later);
+    Unrolled {
+        iterations: earlier,
+    }
+}
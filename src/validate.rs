@@ -0,0 +1,215 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use crate::graph::*;
+
+/// A single way in which a `Graph` is not well formed.
+/// Nodes are referred to by their display name (quantifier name followed by id).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The edges form a cycle; the path is given in causal order and ends where it started.
+    Cycle(Vec<String>),
+    /// `node` has an edge to `other`, which is not part of the graph.
+    DanglingEdge { node: String, other: String },
+    /// `parent` lists `child` as a child but `child` does not list `parent` as a parent, or vice versa.
+    AsymmetricEdge { parent: String, child: String },
+    /// Two nodes in `Graph::nodes` share a name and id.
+    DuplicateId { name: String, id: u32 },
+    /// A node is filed under the wrong name in `Graph::nodes`.
+    MisfiledNode { node: String, key: String },
+    /// A node is in `Graph::nodes` but not in `Graph::sorted`, or vice versa.
+    NotSorted { node: String },
+    /// A node's depth is not one more than the depth of its deepest child.
+    DepthMismatch {
+        node: String,
+        expected: u32,
+        actual: u32,
+    },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::Cycle(path) => write!(f, "cycle: {}", path.join(" -> ")),
+            Violation::DanglingEdge { node, other } => {
+                write!(
+                    f,
+                    "{} has an edge to {}, which is not in the graph",
+                    node, other
+                )
+            }
+            Violation::AsymmetricEdge { parent, child } => {
+                write!(
+                    f,
+                    "edge {} -> {} is only recorded on one side",
+                    parent, child
+                )
+            }
+            Violation::DuplicateId { name, id } => {
+                write!(f, "more than one node named {} with id {}", name, id)
+            }
+            Violation::MisfiledNode { node, key } => {
+                write!(f, "{} is filed under quantifier {}", node, key)
+            }
+            Violation::NotSorted { node } => {
+                write!(f, "{} is missing from either nodes or sorted", node)
+            }
+            Violation::DepthMismatch {
+                node,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} has depth {} but should have {}",
+                node, actual, expected
+            ),
+        }
+    }
+}
+
+impl Graph {
+    fn owns(&self, n: &RNode) -> bool {
+        self.nodes
+            .get(&n.get_name())
+            .is_some_and(|vec| vec.iter().any(|m| m.ptr_eq(n)))
+    }
+
+    /// Checks that the graph is well formed:
+    /// edges are acyclic, recorded on both ends and stay inside the graph,
+    /// ids are unique per name, `nodes` and `sorted` agree and depths are consistent.
+    /// Returns every violation found; an empty list means the graph is safe to analyse.
+    #[allow(clippy::mutable_key_type)]
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = vec![];
+
+        let mut seen: HashSet<RNode> = HashSet::new();
+        for (key, vec) in self.nodes.iter() {
+            for n in vec.iter() {
+                if &n.get_name() != key {
+                    violations.push(Violation::MisfiledNode {
+                        node: n.to_string(),
+                        key: key.clone(),
+                    });
+                }
+                if !seen.insert(n.clone()) {
+                    violations.push(Violation::DuplicateId {
+                        name: n.get_name(),
+                        id: n.get_id(),
+                    });
+                }
+            }
+        }
+
+        let sorted: HashSet<RNode> = self.sorted.iter().cloned().collect();
+        for n in seen.symmetric_difference(&sorted) {
+            violations.push(Violation::NotSorted {
+                node: n.to_string(),
+            });
+        }
+
+        for n in self.sorted.iter() {
+            for p in n.borrow().parents.iter() {
+                if !self.owns(p) {
+                    violations.push(Violation::DanglingEdge {
+                        node: n.to_string(),
+                        other: p.to_string(),
+                    });
+                } else if !p.borrow().children.contains(n) {
+                    violations.push(Violation::AsymmetricEdge {
+                        parent: p.to_string(),
+                        child: n.to_string(),
+                    });
+                }
+            }
+            for c in n.borrow().children.iter() {
+                if !self.owns(c) {
+                    violations.push(Violation::DanglingEdge {
+                        node: n.to_string(),
+                        other: c.to_string(),
+                    });
+                } else if !c.borrow().parents.contains(n) {
+                    violations.push(Violation::AsymmetricEdge {
+                        parent: n.to_string(),
+                        child: c.to_string(),
+                    });
+                }
+            }
+        }
+
+        let cycles = find_cycles(&self.sorted);
+        if cycles.is_empty() {
+            // depths are only meaningful in a DAG
+            for n in self.sorted.iter() {
+                let expected = n
+                    .borrow()
+                    .children
+                    .iter()
+                    .map(|c| c.get_depth() + 1)
+                    .max()
+                    .unwrap_or(0);
+                if n.get_depth() != expected {
+                    violations.push(Violation::DepthMismatch {
+                        node: n.to_string(),
+                        expected,
+                        actual: n.get_depth(),
+                    });
+                }
+            }
+        }
+        violations.extend(cycles);
+
+        violations
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    OnStack,
+    Done,
+}
+
+// iterative depth first search along parent edges, reporting one cycle per back edge
+#[allow(clippy::mutable_key_type)]
+fn find_cycles(nodes: &[RNode]) -> Vec<Violation> {
+    let mut cycles = vec![];
+    let mut marks: HashMap<RNode, Mark> = HashMap::new();
+
+    for root in nodes.iter() {
+        if marks.contains_key(root) {
+            continue;
+        }
+
+        let mut stack: Vec<(RNode, usize)> = vec![(root.clone(), 0)];
+        marks.insert(root.clone(), Mark::OnStack);
+
+        while let Some((n, i)) = stack.last().cloned() {
+            let parent = n.borrow().parents.get(i).cloned();
+            let Some(p) = parent else {
+                marks.insert(n, Mark::Done);
+                stack.pop();
+                continue;
+            };
+            stack.last_mut().expect("stack is not empty").1 += 1;
+
+            match marks.get(&p) {
+                None => {
+                    marks.insert(p.clone(), Mark::OnStack);
+                    stack.push((p, 0));
+                }
+                Some(Mark::OnStack) => {
+                    // stack[j + 1] is a parent of stack[j], so walking the stack backwards is causal order
+                    let start = stack
+                        .iter()
+                        .position(|(m, _)| m == &p)
+                        .expect("nodes marked OnStack are on the stack");
+                    let mut path: Vec<String> = vec![p.to_string()];
+                    path.extend(stack[start..].iter().rev().map(|(m, _)| m.to_string()));
+                    cycles.push(Violation::Cycle(path));
+                }
+                Some(Mark::Done) => {}
+            }
+        }
+    }
+
+    cycles
+}